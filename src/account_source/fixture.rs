@@ -0,0 +1,109 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose, Engine};
+use jupiter_amm_interface::AccountMap;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use super::AccountSource;
+
+/// A directory of `<pubkey>.json` files in the format written by
+/// `solana account <pubkey> --output json`.
+#[derive(Clone, Debug)]
+pub struct FixtureAccountSource {
+    pub dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct UiAccountFile {
+    pubkey: String,
+    account: UiAccount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount {
+    lamports: u64,
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+impl FixtureAccountSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureAccountSource { dir: dir.into() }
+    }
+
+    /// Dumps accounts into the directory, e.g. to capture a pool from RPC.
+    pub fn write_accounts(&self, accounts: &AccountMap) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        for (key, account) in accounts {
+            let file = UiAccountFile {
+                pubkey: key.to_string(),
+                account: UiAccount {
+                    lamports: account.lamports,
+                    data: (
+                        general_purpose::STANDARD.encode(&account.data),
+                        "base64".to_string(),
+                    ),
+                    owner: account.owner.to_string(),
+                    executable: account.executable,
+                    rent_epoch: account.rent_epoch,
+                },
+            };
+
+            fs::write(self.path(key), serde_json::to_string_pretty(&file)?)?;
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, key: &Pubkey) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+impl AccountSource for FixtureAccountSource {
+    fn get_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<AccountMap> {
+        let mut accounts = AccountMap::default();
+
+        for key in keys {
+            let path = self.path(key);
+
+            if path.exists() {
+                accounts.insert(*key, read_account(&path)?);
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+fn read_account(path: &Path) -> anyhow::Result<Account> {
+    let file: UiAccountFile = serde_json::from_str(
+        &fs::read_to_string(path).context(format!("Can't read {}", path.display()))?,
+    )?;
+
+    let (data, encoding) = file.account.data;
+    if encoding != "base64" {
+        return Err(anyhow!(
+            "Unsupported account encoding {encoding} in {}",
+            path.display()
+        ));
+    }
+
+    Ok(Account {
+        lamports: file.account.lamports,
+        data: general_purpose::STANDARD.decode(data)?,
+        owner: Pubkey::from_str(&file.account.owner)?,
+        executable: file.account.executable,
+        rent_epoch: file.account.rent_epoch,
+    })
+}
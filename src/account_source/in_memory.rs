@@ -0,0 +1,24 @@
+use jupiter_amm_interface::AccountMap;
+use solana_sdk::pubkey::Pubkey;
+
+use super::AccountSource;
+
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryAccountSource {
+    pub accounts: AccountMap,
+}
+
+impl InMemoryAccountSource {
+    pub fn new(accounts: AccountMap) -> Self {
+        InMemoryAccountSource { accounts }
+    }
+}
+
+impl AccountSource for InMemoryAccountSource {
+    fn get_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<AccountMap> {
+        Ok(keys
+            .iter()
+            .filter_map(|key| Some((*key, self.accounts.get(key)?.clone())))
+            .collect())
+    }
+}
@@ -0,0 +1,72 @@
+mod fixture;
+mod in_memory;
+#[cfg(feature = "rpc")]
+mod rpc;
+
+pub use fixture::*;
+pub use in_memory::*;
+#[cfg(feature = "rpc")]
+pub use rpc::*;
+
+use anyhow::Context;
+use jupiter_amm_interface::{AccountMap, Amm, AmmContext, ClockRef, KeyedAccount};
+use solana_sdk::{clock::Clock, pubkey::Pubkey, sysvar::SysvarId};
+
+use crate::{PoolAmm, UpdateType};
+
+/// Somewhere accounts can be read from. Tools, tests and services all load
+/// pools through this so they go through the same update phases as Jupiter.
+pub trait AccountSource {
+    /// Accounts that don't exist are left out of the returned map.
+    fn get_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<AccountMap>;
+
+    fn get_clock(&self) -> anyhow::Result<Option<Clock>> {
+        self.get_accounts(&[Clock::id()])?
+            .get(&Clock::id())
+            .map(|account| account.deserialize_data())
+            .transpose()
+            .context("Can't deserialize the clock sysvar")
+    }
+}
+
+impl PoolAmm {
+    /// Builds a quoter for `pool_key` and runs it through every update phase,
+    /// the returned instance is ready to quote.
+    pub fn load(
+        source: &dyn AccountSource,
+        pool_key: Pubkey,
+        params: Option<serde_json::Value>,
+    ) -> anyhow::Result<Self> {
+        let account = source
+            .get_accounts(&[pool_key])?
+            .remove(&pool_key)
+            .context(format!("Pool does not exist: {pool_key}"))?;
+
+        let clock_ref = source.get_clock()?.map(ClockRef::from).unwrap_or_default();
+
+        let mut amm = PoolAmm::from_keyed_account(
+            &KeyedAccount {
+                key: pool_key,
+                account,
+                params,
+            },
+            &AmmContext { clock_ref },
+        )?;
+
+        amm.refresh(source)?;
+
+        Ok(amm)
+    }
+
+    /// Runs update phases until custodies, oracles and mints are all fresh.
+    pub fn refresh(&mut self, source: &dyn AccountSource) -> anyhow::Result<()> {
+        loop {
+            let account_map = source.get_accounts(&self.get_accounts_to_update())?;
+            self.update(&account_map)?;
+
+            if matches!(self.update_type, UpdateType::Custodies) {
+                return Ok(());
+            }
+        }
+    }
+}
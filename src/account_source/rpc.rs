@@ -0,0 +1,36 @@
+use jupiter_amm_interface::AccountMap;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use super::AccountSource;
+
+/// `getMultipleAccounts` is limited to 100 keys per request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+pub struct RpcAccountSource {
+    pub client: RpcClient,
+}
+
+impl RpcAccountSource {
+    pub fn new(url: impl ToString) -> Self {
+        RpcAccountSource {
+            client: RpcClient::new(url.to_string()),
+        }
+    }
+}
+
+impl AccountSource for RpcAccountSource {
+    fn get_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<AccountMap> {
+        let mut accounts = AccountMap::default();
+
+        for keys in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            for (key, account) in keys.iter().zip(self.client.get_multiple_accounts(keys)?) {
+                if let Some(account) = account {
+                    accounts.insert(*key, account);
+                }
+            }
+        }
+
+        Ok(accounts)
+    }
+}
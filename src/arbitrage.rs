@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::PoolAmm;
+
+/// Two ways of getting from one custody token to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArbitragePath {
+    DirectSwap,
+    /// Mint ALP with the input token, then redeem it into the output token.
+    LpRoundTrip,
+}
+
+#[derive(Clone, Debug)]
+pub struct PathComparison {
+    pub amount: u64,
+    /// `None` when the path can't be quoted at this size.
+    pub direct_out_amount: Option<u64>,
+    pub lp_amount: Option<u64>,
+    pub round_trip_out_amount: Option<u64>,
+    /// How much more the round trip pays out than the direct swap, in percent.
+    /// Both legs of the round trip are priced on the same snapshot, so the
+    /// redeem doesn't see the custody and AUM changes of the mint before it.
+    pub round_trip_advantage_pct: Option<Decimal>,
+    /// The path paying out more, ties going to the direct swap.
+    pub cheaper_path: Option<ArbitragePath>,
+}
+
+/// Input amount at which the cheaper path changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathCrossover {
+    /// First amount at which `from` is no longer the cheaper path.
+    pub amount: u64,
+    pub from: Option<ArbitragePath>,
+    pub to: Option<ArbitragePath>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LpArbitrage {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Cheaper path at the smallest sampled size.
+    pub cheaper_path: Option<ArbitragePath>,
+    /// Changes of the cheaper path found over the sampled sizes, by increasing
+    /// amount. Changes between two samples with the same cheaper path are
+    /// missed, see `PoolAmm::lp_arbitrage`.
+    pub crossovers: Vec<PathCrossover>,
+    /// One comparison per sampled size.
+    pub samples: Vec<PathComparison>,
+}
+
+impl LpArbitrage {
+    /// Smallest input amount at which `path` stops being the cheaper one,
+    /// `None` when it never is or stays cheaper up to the largest sampled size.
+    pub fn stops_being_cheaper_at(&self, path: ArbitragePath) -> Option<u64> {
+        self.crossovers
+            .iter()
+            .find(|crossover| crossover.from == Some(path))
+            .map(|crossover| crossover.amount)
+    }
+}
+
+impl PoolAmm {
+    /// Compares, for every ordered pair of custodies, swapping directly with
+    /// going through an ALP mint and redeem. `sizes_usd` are the trade sizes to
+    /// sample, in USD with `USD_DECIMALS`, smallest first.
+    ///
+    /// Between two samples where the cheaper path changes, the exact sizes are
+    /// bisected for, assuming each path is cheaper over a single range there.
+    /// When a path is only cheaper over a range lying between two samples, or
+    /// over several ranges between them, some crossovers are missed; sample
+    /// more sizes to narrow the gaps.
+    pub fn lp_arbitrage(&self, sizes_usd: &[u64]) -> anyhow::Result<Vec<LpArbitrage>> {
+        if sizes_usd.is_empty() || sizes_usd.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow!("Sizes must be increasing and not empty"));
+        }
+
+        let lp_token_mint = self.lp_token_mint.0;
+
+        let mut mints: Vec<(usize, Pubkey)> = self
+            .custodies
+            .iter()
+            .map(|(custody_key, custody)| {
+                (
+                    self.pool.get_token_id(custody_key).unwrap_or(usize::MAX),
+                    custody.mint,
+                )
+            })
+            .collect();
+        mints.sort();
+
+        let mut arbitrages = vec![];
+
+        for (_, input_mint) in &mints {
+            for (_, output_mint) in &mints {
+                if input_mint == output_mint {
+                    continue;
+                }
+
+                let round_trip_allowed = self.is_route_allowed(input_mint, &lp_token_mint)
+                    && self.is_route_allowed(&lp_token_mint, output_mint);
+                if !round_trip_allowed && !self.is_route_allowed(input_mint, output_mint) {
+                    continue;
+                }
+
+                arbitrages.push(self.pair_lp_arbitrage(*input_mint, *output_mint, sizes_usd)?);
+            }
+        }
+
+        Ok(arbitrages)
+    }
+
+    fn pair_lp_arbitrage(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        sizes_usd: &[u64],
+    ) -> anyhow::Result<LpArbitrage> {
+        let (_, custody, price) = self.get_custody_and_oracle(input_mint)?;
+
+        let samples = sizes_usd
+            .iter()
+            .map(|size_usd| {
+                let amount = price.get_token_amount(*size_usd, custody.decimals)?;
+                self.compare_paths(input_mint, output_mint, amount)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let cheaper_path = |amount| -> anyhow::Result<Option<ArbitragePath>> {
+            Ok(self
+                .compare_paths(input_mint, output_mint, amount)?
+                .cheaper_path)
+        };
+
+        let mut crossovers = vec![];
+
+        for pair in samples.windows(2) {
+            let (mut from_amount, mut from) = (pair[0].amount, pair[0].cheaper_path);
+
+            while from != pair[1].cheaper_path {
+                let (mut cheaper, mut not_cheaper) = (from_amount, pair[1].amount);
+
+                while not_cheaper - cheaper > 1 {
+                    let amount = cheaper + (not_cheaper - cheaper) / 2;
+
+                    if cheaper_path(amount)? == from {
+                        cheaper = amount;
+                    } else {
+                        not_cheaper = amount;
+                    }
+                }
+
+                let to = cheaper_path(not_cheaper)?;
+                crossovers.push(PathCrossover {
+                    amount: not_cheaper,
+                    from,
+                    to,
+                });
+
+                (from_amount, from) = (not_cheaper, to);
+            }
+        }
+
+        Ok(LpArbitrage {
+            input_mint,
+            output_mint,
+            cheaper_path: samples[0].cheaper_path,
+            crossovers,
+            samples,
+        })
+    }
+
+    /// Compares both paths from `input_mint` to `output_mint` for `amount`. A
+    /// path that can't be quoted, e.g. because it would push a custody past its
+    /// ratio limits, is never the cheaper one.
+    ///
+    /// The redeem is quoted on the same state as the mint rather than on the
+    /// state the mint leaves behind, as if both legs were separate trades
+    /// against the current pool. The round trip's fees and ratio limits are
+    /// slightly off for sizes large enough to move the custody ratios.
+    pub fn compare_paths(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<PathComparison> {
+        let lp_token_mint = self.lp_token_mint.0;
+        let out_amount = |input_mint: Pubkey, output_mint: Pubkey, amount: u64| {
+            self.quote(&QuoteParams {
+                amount,
+                input_mint,
+                output_mint,
+                swap_mode: SwapMode::ExactIn,
+            })
+            .ok()
+            .map(|quote| quote.out_amount)
+        };
+
+        let direct_out_amount = out_amount(input_mint, output_mint, amount);
+        let lp_amount = out_amount(input_mint, lp_token_mint, amount);
+        let round_trip_out_amount =
+            lp_amount.and_then(|lp_amount| out_amount(lp_token_mint, output_mint, lp_amount));
+
+        let round_trip_advantage_pct = match (direct_out_amount, round_trip_out_amount) {
+            (Some(direct), Some(round_trip)) if direct > 0 => Some(
+                Decimal::ONE_HUNDRED
+                    .checked_mul(Decimal::from(round_trip) - Decimal::from(direct))
+                    .and_then(|per| per.checked_div(Decimal::from(direct)))
+                    .context("Can't calculate round trip advantage")?,
+            ),
+            _ => None,
+        };
+
+        let cheaper_path = match (direct_out_amount, round_trip_out_amount) {
+            (Some(direct), Some(round_trip)) if round_trip > direct => {
+                Some(ArbitragePath::LpRoundTrip)
+            }
+            (Some(_), _) => Some(ArbitragePath::DirectSwap),
+            (None, Some(_)) => Some(ArbitragePath::LpRoundTrip),
+            (None, None) => None,
+        };
+
+        Ok(PathComparison {
+            amount,
+            direct_out_amount,
+            lp_amount,
+            round_trip_out_amount,
+            round_trip_advantage_pct,
+            cheaper_path,
+        })
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::{anyhow, Context};
+use num_traits::{FromPrimitive, Zero};
+use rust_decimal::Decimal;
+
+use crate::{
+    utils::{decimal_to_usd, price_to_decimal, usd_to_decimal},
+    PoolAmm, PRICE_DECIMALS, RATE_DECIMALS,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct AumDrift {
+    /// `pool.aum_usd` as last written by the program.
+    pub stored_aum_usd: u128,
+    /// AUM recomputed from the cached custodies and oracle prices.
+    pub live_aum_usd: u128,
+    pub drift_usd: i128,
+    pub drift_pct: Decimal,
+}
+
+impl PoolAmm {
+    /// Recomputes the pool AUM the way the program does when executing: value of
+    /// the assets owned by each custody at the current oracle price, minus the
+    /// unrealized PnL of open long and short positions, plus the borrow fees
+    /// they accrued and haven't paid yet.
+    pub fn live_aum_usd(&self) -> anyhow::Result<u128> {
+        let mut aum_usd = Decimal::ZERO;
+
+        for custody in self.custodies.values() {
+            let oracle_price = self
+                .oracle_prices
+                .get(&custody.oracle.oracle_account)
+                .context(format!(
+                    "Oracle does not exist: {}",
+                    custody.oracle.oracle_account
+                ))?;
+
+            let owned_usd =
+                oracle_price.get_asset_amount_usd(custody.assets.owned, custody.decimals)?;
+            let price = price_to_decimal(oracle_price)?;
+
+            let long_pnl = positions_pnl(
+                custody.long_positions.size_usd,
+                custody.long_positions.weighted_price.to_u128(),
+                price,
+            )?;
+            let short_pnl = positions_pnl(
+                custody.short_positions.size_usd,
+                custody.short_positions.weighted_price.to_u128(),
+                price,
+            )?;
+
+            let cumulative_interest = self.cumulative_interest(custody)?;
+            let borrow_fees = accrued_borrow_fees(
+                custody.long_positions.size_usd,
+                custody
+                    .long_positions
+                    .cumulative_interest_snapshot
+                    .to_u128(),
+                custody.long_positions.cumulative_interest_usd,
+                cumulative_interest,
+            )? + accrued_borrow_fees(
+                custody.short_positions.size_usd,
+                custody
+                    .short_positions
+                    .cumulative_interest_snapshot
+                    .to_u128(),
+                custody.short_positions.cumulative_interest_usd,
+                cumulative_interest,
+            )?;
+
+            aum_usd += usd_to_decimal(owned_usd as u128)? - long_pnl + short_pnl + borrow_fees;
+        }
+
+        Ok(u128::try_from(decimal_to_usd(aum_usd)?.max(0))?)
+    }
+
+    pub fn stored_aum_usd(&self) -> u128 {
+        self.pool.aum_usd.to_u128()
+    }
+
+    /// Why ALP is priced from the stored AUM, `None` while the live one could be
+    /// recomputed at the last oracle update.
+    pub fn aum_error(&self) -> Option<&str> {
+        self.aum_error.as_deref()
+    }
+
+    /// Fails liquidity quotes instead of pricing ALP from a stale AUM.
+    pub(crate) fn ensure_live_aum(&self) -> anyhow::Result<()> {
+        match &self.aum_error {
+            Some(err) => Err(anyhow!(
+                "ALP is priced from the stored AUM, the live one can't be recomputed: {err}"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn aum_drift(&self) -> anyhow::Result<AumDrift> {
+        let stored_aum_usd = self.stored_aum_usd();
+        let live_aum_usd = self
+            .live_aum_usd()
+            .context("Can't recompute the live AUM, ALP is priced from the stored one")?;
+        let drift_usd = live_aum_usd as i128 - stored_aum_usd as i128;
+
+        let drift_pct = if stored_aum_usd.is_zero() {
+            Decimal::ZERO
+        } else {
+            Decimal::ONE_HUNDRED
+                .checked_mul(Decimal::from_i128(drift_usd).context("Can't convert drift")?)
+                .and_then(|per| per.checked_div(Decimal::from_u128(stored_aum_usd)?))
+                .context("Can't calculate drift percentage")?
+        };
+
+        Ok(AumDrift {
+            stored_aum_usd,
+            live_aum_usd,
+            drift_usd,
+            drift_pct,
+        })
+    }
+}
+
+/// Unrealized PnL, from the long side's point of view, of all positions of one
+/// side of a custody. `weighted_price` is the sum of entry price * size, so the
+/// average entry price is `weighted_price / size_usd`.
+fn positions_pnl(size_usd: u64, weighted_price: u128, price: Decimal) -> anyhow::Result<Decimal> {
+    if size_usd.is_zero() || weighted_price.is_zero() {
+        return Ok(Decimal::ZERO);
+    }
+
+    let size = usd_to_decimal(size_usd as u128)?;
+    let entry_price = Decimal::from_u128(weighted_price)
+        .and_then(|weighted| weighted.checked_div(Decimal::from_u64(size_usd)?))
+        .and_then(|price| price.checked_div(Decimal::TEN.powu(PRICE_DECIMALS as u64)))
+        .context("Can't calculate average entry price")?;
+
+    size.checked_mul(price - entry_price)
+        .and_then(|pnl| pnl.checked_div(entry_price))
+        .context("Can't calculate unrealized pnl")
+}
+
+/// Borrow fees owed by all positions of one side of a custody: those settled into
+/// `cumulative_interest_usd` at their last update, plus what accrued on their
+/// size since `cumulative_interest_snapshot`.
+fn accrued_borrow_fees(
+    size_usd: u64,
+    cumulative_interest_snapshot: u128,
+    cumulative_interest_usd: u64,
+    cumulative_interest: u128,
+) -> anyhow::Result<Decimal> {
+    let settled = usd_to_decimal(cumulative_interest_usd as u128)?;
+    let interest = cumulative_interest.saturating_sub(cumulative_interest_snapshot);
+
+    if size_usd.is_zero() || interest.is_zero() {
+        return Ok(settled);
+    }
+
+    let size = usd_to_decimal(size_usd as u128)?;
+
+    Decimal::from_u128(interest)
+        .map(|interest| interest / Decimal::TEN.powu(RATE_DECIMALS as u64))
+        .and_then(|rate| size.checked_mul(rate))
+        .map(|accrued| settled + accrued)
+        .context("Can't calculate accrued borrow fees")
+}
@@ -0,0 +1,319 @@
+use std::{io::Write, sync::atomic::Ordering};
+
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{AccountMap, Amm, QuoteParams, SwapMode};
+use rust_decimal::Decimal;
+use solana_sdk::{account::Account, clock::Clock, pubkey::Pubkey, sysvar, sysvar::SysvarId};
+
+use crate::{utils::usd_to_decimal, ExecutionRecord, InMemoryAccountSource, Operation, PoolAmm};
+
+/// Accounts of the pool at one slot, and what was executed against it.
+#[derive(Clone, Debug)]
+pub struct BacktestStep {
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+    /// Pool, custodies, oracles and mints as of `slot`.
+    pub accounts: AccountMap,
+    /// Swaps and liquidity changes executed at `slot`, e.g. from `parse_logs`.
+    pub executions: Vec<ExecutionRecord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BacktestRowKind {
+    /// One of the configured quotes.
+    Quote,
+    /// The quote of an execution in the dataset, replayed against the step.
+    Execution,
+}
+
+#[derive(Clone, Debug)]
+pub struct BacktestRow {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+    pub kind: BacktestRowKind,
+    /// `Pubkey::default()` when an execution's custody isn't in the pool, see
+    /// `error`.
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount: u64,
+    pub aum_usd: Decimal,
+    /// `None` while no ALP is in circulation.
+    pub lp_price_usd: Option<Decimal>,
+    /// `None` when the quote failed, see `error`.
+    pub out_amount: Option<u64>,
+    pub fee_amount: Option<u64>,
+    pub fee_pct: Option<Decimal>,
+    pub executed_out_amount: Option<u64>,
+    /// Executed minus quoted.
+    pub out_amount_diff: Option<i128>,
+    pub out_amount_diff_pct: Option<Decimal>,
+    /// Out amount of minting ALP with the input and redeeming it into the
+    /// output, for swaps only.
+    pub round_trip_out_amount: Option<u64>,
+    /// How much more the ALP round trip pays out than the swap, in percent.
+    pub round_trip_advantage_pct: Option<Decimal>,
+    /// Why the ALP round trip couldn't be compared, apart from `error` which is
+    /// about the row's own quote.
+    pub round_trip_error: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub rows: Vec<BacktestRow>,
+}
+
+/// Replays recorded pool states through `PoolAmm::update` and quotes the same
+/// requests at every step.
+pub struct Backtest {
+    pub pool_key: Pubkey,
+    pub params: Option<serde_json::Value>,
+    pub quotes: Vec<QuoteParams>,
+}
+
+impl Backtest {
+    pub fn new(
+        pool_key: Pubkey,
+        params: Option<serde_json::Value>,
+        quotes: Vec<QuoteParams>,
+    ) -> Self {
+        Backtest {
+            pool_key,
+            params,
+            quotes,
+        }
+    }
+
+    /// Runs the steps in order. A quote failing at a step is reported in its
+    /// row, a step that can't be applied aborts the run.
+    pub fn run(
+        &self,
+        steps: impl IntoIterator<Item = BacktestStep>,
+    ) -> anyhow::Result<BacktestReport> {
+        let mut amm: Option<PoolAmm> = None;
+        let mut report = BacktestReport::default();
+
+        for step in steps {
+            let amm = match amm.as_mut() {
+                Some(amm) => {
+                    let last_slot = amm.clock_ref.slot.load(Ordering::Relaxed);
+                    if step.slot < last_slot {
+                        return Err(anyhow!(
+                            "Steps are out of order, slot {} comes after {last_slot}",
+                            step.slot
+                        ));
+                    }
+
+                    amm.clock_ref.slot.store(step.slot, Ordering::Relaxed);
+                    amm.clock_ref.epoch.store(step.epoch, Ordering::Relaxed);
+                    amm.clock_ref
+                        .unix_timestamp
+                        .store(step.unix_timestamp, Ordering::Relaxed);
+                    amm.refresh(&InMemoryAccountSource::new(step.accounts.clone()))
+                        .context(format!("Can't update pool at slot {}", step.slot))?;
+
+                    amm
+                }
+                None => {
+                    // Loading reads the step's clock from the accounts
+                    let clock = Clock {
+                        slot: step.slot,
+                        epoch: step.epoch,
+                        unix_timestamp: step.unix_timestamp,
+                        ..Clock::default()
+                    };
+                    let mut accounts = step.accounts.clone();
+                    accounts.insert(Clock::id(), Account::new_data(1, &clock, &sysvar::ID)?);
+
+                    amm.insert(
+                        PoolAmm::load(
+                            &InMemoryAccountSource::new(accounts),
+                            self.pool_key,
+                            self.params.clone(),
+                        )
+                        .context(format!("Can't load pool at slot {}", step.slot))?,
+                    )
+                }
+            };
+
+            report.rows.extend(amm.backtest_step(&step, &self.quotes)?);
+        }
+
+        Ok(report)
+    }
+}
+
+impl PoolAmm {
+    fn backtest_step(
+        &self,
+        step: &BacktestStep,
+        quotes: &[QuoteParams],
+    ) -> anyhow::Result<Vec<BacktestRow>> {
+        let aum_usd = usd_to_decimal(self.aum_usd)?;
+        let lp_price_usd = self.lp_price_usd()?;
+
+        let row = |kind: BacktestRowKind, params: &QuoteParams| BacktestRow {
+            slot: step.slot,
+            unix_timestamp: step.unix_timestamp,
+            kind,
+            input_mint: params.input_mint,
+            output_mint: params.output_mint,
+            amount: params.amount,
+            aum_usd,
+            lp_price_usd,
+            out_amount: None,
+            fee_amount: None,
+            fee_pct: None,
+            executed_out_amount: None,
+            out_amount_diff: None,
+            out_amount_diff_pct: None,
+            round_trip_out_amount: None,
+            round_trip_advantage_pct: None,
+            round_trip_error: None,
+            error: None,
+        };
+
+        let mut rows = vec![];
+
+        for params in quotes {
+            let mut row = row(BacktestRowKind::Quote, params);
+
+            match self.quote(params) {
+                Ok(quote) => {
+                    row.out_amount = Some(quote.out_amount);
+                    row.fee_amount = Some(quote.fee_amount);
+                    row.fee_pct = Some(quote.fee_pct);
+                }
+                Err(e) => row.error = Some(e.to_string()),
+            }
+
+            self.compare_round_trip(&mut row);
+            rows.push(row);
+        }
+
+        for execution in &step.executions {
+            let mints = self
+                .execution_mint(execution.custody_in)
+                .and_then(|input_mint| {
+                    Ok((input_mint, self.execution_mint(execution.custody_out)?))
+                });
+            let (input_mint, output_mint) = mints.as_ref().copied().unwrap_or_default();
+
+            let params = QuoteParams {
+                amount: execution.in_amount,
+                input_mint,
+                output_mint,
+                swap_mode: SwapMode::ExactIn,
+            };
+            let mut row = row(BacktestRowKind::Execution, &params);
+            row.executed_out_amount = Some(execution.out_amount);
+
+            match mints
+                .and_then(|_| self.quote(&params))
+                .and_then(|quote| Ok((execution.diff(&quote)?, quote)))
+            {
+                Ok((diff, quote)) => {
+                    row.out_amount = Some(quote.out_amount);
+                    row.fee_amount = Some(quote.fee_amount);
+                    row.fee_pct = Some(quote.fee_pct);
+                    row.out_amount_diff = Some(diff.out_amount_diff);
+                    row.out_amount_diff_pct = Some(diff.out_amount_diff_pct);
+                }
+                Err(e) => row.error = Some(e.to_string()),
+            }
+
+            self.compare_round_trip(&mut row);
+            rows.push(row);
+        }
+
+        Ok(rows)
+    }
+
+    /// Fills in the ALP round trip of swap rows.
+    fn compare_round_trip(&self, row: &mut BacktestRow) {
+        if self.get_operation(&row.input_mint, &row.output_mint) != Operation::Swap {
+            return;
+        }
+
+        match self.compare_paths(row.input_mint, row.output_mint, row.amount) {
+            Ok(comparison) => {
+                row.round_trip_out_amount = comparison.round_trip_out_amount;
+                row.round_trip_advantage_pct = comparison.round_trip_advantage_pct;
+            }
+            Err(e) => row.round_trip_error = Some(e.to_string()),
+        }
+    }
+
+    /// Mint of an execution's custody, the LP mint when there is none.
+    fn execution_mint(&self, custody_key: Option<Pubkey>) -> anyhow::Result<Pubkey> {
+        match custody_key {
+            Some(custody_key) => self
+                .custodies
+                .get(&custody_key)
+                .map(|custody| custody.mint)
+                .context(format!("Custody does not exist: {custody_key}")),
+            None => Ok(self.lp_token_mint.0),
+        }
+    }
+}
+
+impl BacktestReport {
+    pub const CSV_HEADER: &'static str = "slot,unix_timestamp,kind,input_mint,output_mint,amount,\
+aum_usd,lp_price_usd,out_amount,fee_amount,fee_pct,executed_out_amount,out_amount_diff,\
+out_amount_diff_pct,round_trip_out_amount,round_trip_advantage_pct,round_trip_error,error";
+
+    /// Writes one line per row, empty cells standing for missing values.
+    pub fn write_csv(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        writeln!(writer, "{}", Self::CSV_HEADER)?;
+
+        for row in &self.rows {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                row.slot,
+                row.unix_timestamp,
+                match row.kind {
+                    BacktestRowKind::Quote => "quote",
+                    BacktestRowKind::Execution => "execution",
+                },
+                row.input_mint,
+                row.output_mint,
+                row.amount,
+                row.aum_usd,
+                cell(row.lp_price_usd),
+                cell(row.out_amount),
+                cell(row.fee_amount),
+                cell(row.fee_pct),
+                cell(row.executed_out_amount),
+                cell(row.out_amount_diff),
+                cell(row.out_amount_diff_pct),
+                cell(row.round_trip_out_amount),
+                cell(row.round_trip_advantage_pct),
+                text_cell(&row.round_trip_error),
+                text_cell(&row.error),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_csv(&self) -> anyhow::Result<String> {
+        let mut csv = vec![];
+        self.write_csv(&mut csv)?;
+
+        Ok(String::from_utf8(csv)?)
+    }
+}
+
+fn cell<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quoted, as errors may hold commas.
+fn text_cell(value: &Option<String>) -> String {
+    value
+        .as_ref()
+        .map(|e| format!("\"{}\"", e.replace('"', "\"\"")))
+        .unwrap_or_default()
+}
@@ -0,0 +1,232 @@
+use std::path::PathBuf;
+
+use adrena::state::{custody::Custody, oracle::OracleType, pool::Pool};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::anyhow;
+use clap::{Parser, Subcommand, ValueEnum};
+use jupiter_adrena::{
+    oracle, AccountSource, FixtureAccountSource, PoolAmm, RpcAccountSource, TokenMint,
+};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Parser)]
+#[command(about = "Quote and inspect Adrena pools the way Jupiter sees them")]
+struct Cli {
+    /// JSON-RPC endpoint to read accounts from
+    #[arg(long, conflicts_with = "fixtures")]
+    rpc_url: Option<String>,
+
+    /// Directory of `<pubkey>.json` account dumps to read accounts from
+    #[arg(long)]
+    fixtures: Option<PathBuf>,
+
+    /// Pool account
+    #[arg(long)]
+    pool: Pubkey,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    ExactIn,
+    ExactOut,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Quote a single route
+    Quote {
+        #[arg(long)]
+        input: Pubkey,
+        #[arg(long)]
+        output: Pubkey,
+        #[arg(long)]
+        amount: u64,
+        /// Only exact in is supported by Adrena pools
+        #[arg(long, value_enum, default_value_t = Mode::ExactIn)]
+        mode: Mode,
+    },
+    /// Quote a route at increasing sizes
+    Ladder {
+        #[arg(long)]
+        input: Pubkey,
+        #[arg(long)]
+        output: Pubkey,
+        /// Smallest amount quoted
+        #[arg(long)]
+        start: u64,
+        /// Multiplier between two steps
+        #[arg(long, default_value_t = 10)]
+        factor: u64,
+        #[arg(long, default_value_t = 6)]
+        steps: u32,
+        #[arg(long, value_enum, default_value_t = Mode::ExactIn)]
+        mode: Mode,
+    },
+    /// List the accounts both phases of an `update` need
+    Accounts,
+    /// Print the account metas of a route
+    Metas {
+        #[arg(long)]
+        input: Pubkey,
+        #[arg(long)]
+        output: Pubkey,
+        #[arg(long)]
+        amount: u64,
+        /// Wallet signing the swap, its associated token accounts are used
+        #[arg(long)]
+        owner: Pubkey,
+    },
+    /// Decode the pool or one of its pool, custody, oracle or mint accounts
+    Inspect {
+        #[arg(long)]
+        account: Option<Pubkey>,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    let source: Box<dyn AccountSource> = match (cli.rpc_url, cli.fixtures) {
+        (Some(rpc_url), None) => Box::new(RpcAccountSource::new(rpc_url)),
+        (None, Some(dir)) => Box::new(FixtureAccountSource::new(dir)),
+        _ => return Err(anyhow!("One of --rpc-url or --fixtures is required")),
+    };
+
+    let mut amm = PoolAmm::load(source.as_ref(), cli.pool, None)?;
+
+    match cli.command {
+        Command::Quote {
+            input,
+            output,
+            amount,
+            mode,
+        } => {
+            let quote = amm.quote(&QuoteParams {
+                amount,
+                input_mint: input,
+                output_mint: output,
+                swap_mode: swap_mode(mode)?,
+            })?;
+            println!("{quote:#?}");
+        }
+        Command::Ladder {
+            input,
+            output,
+            start,
+            factor,
+            steps,
+            mode,
+        } => {
+            let swap_mode = swap_mode(mode)?;
+            println!("amount,out_amount,fee_amount,fee_pct");
+
+            let mut amount = start;
+            for _ in 0..steps {
+                match amm.quote(&QuoteParams {
+                    amount,
+                    input_mint: input,
+                    output_mint: output,
+                    swap_mode,
+                }) {
+                    Ok(quote) => println!(
+                        "{amount},{},{},{}",
+                        quote.out_amount, quote.fee_amount, quote.fee_pct
+                    ),
+                    Err(err) => println!("{amount},,,{err}"),
+                }
+
+                amount = amount.saturating_mul(factor);
+            }
+        }
+        Command::Accounts => {
+            // The oracles and mints of the second phase come from the custodies
+            // read by the first one
+            for phase in ["custodies", "oracles and tokens"] {
+                let keys = amm.get_accounts_to_update();
+
+                println!("{phase}:");
+                for key in &keys {
+                    println!("  {key}");
+                }
+
+                amm.update(&source.get_accounts(&keys)?)?;
+            }
+        }
+        Command::Metas {
+            input,
+            output,
+            amount,
+            owner,
+        } => {
+            let metas = amm.get_swap_and_account_metas(&SwapParams {
+                in_amount: amount,
+                out_amount: 0,
+                source_mint: input,
+                destination_mint: output,
+                source_token_account: amm.get_user_token_account(&owner, &input),
+                destination_token_account: amm.get_user_token_account(&owner, &output),
+                token_transfer_authority: owner,
+                open_order_address: None,
+                quote_mint_to_referrer: None,
+                jupiter_program_id: &Pubkey::default(),
+                missing_dynamic_accounts_as_default: false,
+            })?;
+
+            for meta in metas.account_metas {
+                println!(
+                    "{} {}{}",
+                    meta.pubkey,
+                    if meta.is_writable { "w" } else { "-" },
+                    if meta.is_signer { "s" } else { "-" }
+                );
+            }
+        }
+        Command::Inspect { account } => {
+            inspect(source.as_ref(), &amm, account.unwrap_or(cli.pool))?
+        }
+    }
+
+    Ok(())
+}
+
+fn swap_mode(mode: Mode) -> anyhow::Result<SwapMode> {
+    match mode {
+        Mode::ExactIn => Ok(SwapMode::ExactIn),
+        Mode::ExactOut => Err(anyhow!(
+            "Exact out quotes are not supported by Adrena pools, use --mode exact-in"
+        )),
+    }
+}
+
+fn inspect(source: &dyn AccountSource, amm: &PoolAmm, key: Pubkey) -> anyhow::Result<()> {
+    let account = source
+        .get_accounts(&[key])?
+        .remove(&key)
+        .ok_or(anyhow!("Account does not exist: {key}"))?;
+    let data = &account.data;
+
+    if account.owner == amm.program_id() && data.starts_with(&Pool::DISCRIMINATOR) {
+        println!("{:#?}", Pool::try_deserialize(&mut &data[..])?);
+        println!("live aum: {:#?}", amm.aum_drift()?);
+        println!("lp nav: {:#?}", amm.lp_nav_breakdown()?);
+    } else if account.owner == amm.program_id() && data.starts_with(&Custody::DISCRIMINATOR) {
+        println!("{:#?}", Custody::try_deserialize(&mut &data[..])?);
+    } else if let Ok(token_mint) = TokenMint::unpack(&key, &account) {
+        println!("{token_mint:#?}");
+    } else {
+        let oracle_type = if account.owner == amm.program_id() {
+            OracleType::Custom
+        } else {
+            OracleType::Pyth
+        };
+
+        let price = oracle::parse_oracle_account(oracle_type, &key, &account, &amm.program_id())?;
+        println!("{price:#?}");
+    }
+
+    Ok(())
+}
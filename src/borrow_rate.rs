@@ -0,0 +1,203 @@
+use adrena::state::custody::Custody;
+use anyhow::{anyhow, Context};
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{PoolAmm, RATE_DECIMALS, SECONDS_PER_HOUR};
+
+#[derive(Clone, Debug)]
+pub struct BorrowRate {
+    pub custody: Pubkey,
+    pub mint: Pubkey,
+    /// `assets.locked / assets.owned`, in percent.
+    pub utilization_pct: Decimal,
+    /// Hourly rate the custody's curve gives at this utilization, in percent.
+    pub hourly_rate_pct: Decimal,
+    /// Hourly rate stored by the program at its last update, in percent.
+    pub stored_hourly_rate_pct: Decimal,
+}
+
+#[derive(Clone, Debug)]
+pub struct InterestProjection {
+    pub custody: Pubkey,
+    pub horizon_seconds: i64,
+    pub hourly_rate_pct: Decimal,
+    /// Interest accrued per dollar borrowed over the horizon, in percent.
+    pub accrued_pct: Decimal,
+    /// `borrow_rate_state.cumulative_interest` at the end of the horizon.
+    pub cumulative_interest: u128,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UtilizationPoint {
+    pub utilization_pct: Decimal,
+    pub hourly_rate_pct: Decimal,
+}
+
+impl PoolAmm {
+    pub fn borrow_rate(&self, mint: Pubkey) -> anyhow::Result<BorrowRate> {
+        let (custody_key, custody, _) = self.get_custody_and_oracle(mint)?;
+
+        self.borrow_rate_at(custody_key, custody, custody.assets.owned)
+    }
+
+    /// Borrow rates of every custody, in pool order.
+    pub fn borrow_rates(&self) -> anyhow::Result<Vec<BorrowRate>> {
+        let mut rates = self
+            .custodies
+            .iter()
+            .map(|(custody_key, custody)| {
+                self.borrow_rate_at(*custody_key, custody, custody.assets.owned)
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        rates.sort_by_key(|r| self.pool.get_token_id(&r.custody).unwrap_or(usize::MAX));
+
+        Ok(rates)
+    }
+
+    /// Borrow rate of the custody once `amount` of its tokens has left the pool,
+    /// e.g. by redeeming ALP into it. Redemptions that push utilization up make
+    /// borrowing, and so holding ALP against that custody, more expensive.
+    pub fn borrow_rate_after_redeem(
+        &self,
+        mint: Pubkey,
+        amount: u64,
+    ) -> anyhow::Result<BorrowRate> {
+        let (custody_key, custody, _) = self.get_custody_and_oracle(mint)?;
+
+        let owned = custody
+            .assets
+            .owned
+            .checked_sub(amount)
+            .ok_or(anyhow!("Custody {custody_key} owns less than {amount}"))?;
+
+        self.borrow_rate_at(custody_key, custody, owned)
+    }
+
+    /// Projects interest accrued over `horizon_seconds` at the rate implied by
+    /// the current utilization, assuming it stays constant.
+    pub fn project_interest(
+        &self,
+        mint: Pubkey,
+        horizon_seconds: i64,
+    ) -> anyhow::Result<InterestProjection> {
+        let (custody_key, custody, _) = self.get_custody_and_oracle(mint)?;
+
+        if horizon_seconds < 0 {
+            return Err(anyhow!("Horizon must not be negative"));
+        }
+
+        let hourly_rate = hourly_rate(custody, utilization(custody, custody.assets.owned)?)?;
+        let accrued = hourly_rate
+            .checked_mul(Decimal::from(horizon_seconds))
+            .and_then(|accrued| accrued.checked_div(Decimal::from(SECONDS_PER_HOUR)))
+            .context("Can't project interest")?;
+
+        let cumulative_interest = self.cumulative_interest(custody)?
+            + accrued
+                .checked_mul(rate_power())
+                .and_then(|accrued| accrued.floor().to_u128())
+                .context("Can't project cumulative interest")?;
+
+        Ok(InterestProjection {
+            custody: custody_key,
+            horizon_seconds,
+            hourly_rate_pct: hourly_rate * Decimal::ONE_HUNDRED,
+            accrued_pct: accrued * Decimal::ONE_HUNDRED,
+            cumulative_interest,
+        })
+    }
+
+    /// The custody's borrow rate curve sampled at `points` evenly spaced
+    /// utilizations from 0% to 100%.
+    pub fn utilization_curve(
+        &self,
+        mint: Pubkey,
+        points: usize,
+    ) -> anyhow::Result<Vec<UtilizationPoint>> {
+        let (_, custody, _) = self.get_custody_and_oracle(mint)?;
+
+        if points < 2 {
+            return Err(anyhow!("A curve needs at least 2 points"));
+        }
+
+        (0..points)
+            .map(|i| {
+                let utilization = Decimal::from(i) / Decimal::from(points - 1);
+
+                Ok(UtilizationPoint {
+                    utilization_pct: utilization * Decimal::ONE_HUNDRED,
+                    hourly_rate_pct: hourly_rate(custody, utilization)? * Decimal::ONE_HUNDRED,
+                })
+            })
+            .collect()
+    }
+
+    fn borrow_rate_at(
+        &self,
+        custody_key: Pubkey,
+        custody: &Custody,
+        owned: u64,
+    ) -> anyhow::Result<BorrowRate> {
+        let utilization = utilization(custody, owned)?;
+
+        Ok(BorrowRate {
+            custody: custody_key,
+            mint: custody.mint,
+            utilization_pct: utilization * Decimal::ONE_HUNDRED,
+            hourly_rate_pct: hourly_rate(custody, utilization)? * Decimal::ONE_HUNDRED,
+            stored_hourly_rate_pct: rate_to_decimal(custody.borrow_rate_state.current_rate)?
+                * Decimal::ONE_HUNDRED,
+        })
+    }
+}
+
+/// Share of `owned` locked by open positions, capped at 1.
+fn utilization(custody: &Custody, owned: u64) -> anyhow::Result<Decimal> {
+    if owned.is_zero() {
+        return Ok(Decimal::ZERO);
+    }
+
+    Decimal::from(custody.assets.locked)
+        .checked_div(Decimal::from(owned))
+        .map(|utilization| utilization.min(Decimal::ONE))
+        .context("Can't calculate utilization")
+}
+
+/// Two-slope curve: from `base_rate`, the rate rises by `slope1` up to the
+/// optimal utilization, then by `slope2` from there to full utilization.
+fn hourly_rate(custody: &Custody, utilization: Decimal) -> anyhow::Result<Decimal> {
+    let params = &custody.borrow_rate;
+    let base_rate = rate_to_decimal(params.base_rate)?;
+    let slope1 = rate_to_decimal(params.slope1)?;
+    let slope2 = rate_to_decimal(params.slope2)?;
+    let optimal_utilization = rate_to_decimal(params.optimal_utilization)?;
+
+    if utilization <= optimal_utilization {
+        if optimal_utilization.is_zero() {
+            return Ok(base_rate);
+        }
+
+        return Ok(base_rate + slope1 * utilization / optimal_utilization);
+    }
+
+    if optimal_utilization >= Decimal::ONE {
+        return Ok(base_rate + slope1);
+    }
+
+    Ok(base_rate
+        + slope1
+        + slope2 * (utilization - optimal_utilization) / (Decimal::ONE - optimal_utilization))
+}
+
+fn rate_to_decimal(rate: u64) -> anyhow::Result<Decimal> {
+    Decimal::from_u64(rate)
+        .and_then(|rate| rate.checked_div(rate_power()))
+        .context("Can't convert rate")
+}
+
+fn rate_power() -> Decimal {
+    Decimal::TEN.powu(RATE_DECIMALS as u64)
+}
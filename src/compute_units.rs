@@ -0,0 +1,47 @@
+use crate::{Operation, PoolAmm, TOKEN_2022_ID};
+
+/// Solana per-transaction compute limit, estimates never go above it.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Handler cost excluding the per-custody AUM work, including the staking
+/// reward accounting (fee conversion and the two reward vault transfers).
+///
+/// These and the constants below are estimates, not yet measured: they should
+/// be set from the units `tests/compute_units_program.rs` reports when run
+/// against the program.
+const SWAP_BASE_COMPUTE_UNITS: u32 = 140_000;
+const ADD_LIQUIDITY_BASE_COMPUTE_UNITS: u32 = 120_000;
+const REMOVE_LIQUIDITY_BASE_COMPUTE_UNITS: u32 = 115_000;
+
+/// Loading a custody and its oracle and adding it to the AUM.
+const PER_CUSTODY_COMPUTE_UNITS: u32 = 12_000;
+
+/// Extra cost of a Token-2022 `transfer_checked` over an SPL Token transfer.
+const TOKEN_2022_TRANSFER_COMPUTE_UNITS: u32 = 8_000;
+
+/// Headroom on top of the estimate, in percent.
+const MARGIN_PCT: u32 = 15;
+
+/// Compute budget to request for `operation` on this pool. Every handler
+/// recomputes the AUM, loading each custody and its oracle.
+pub fn estimate_compute_units(operation: Operation, amm: &PoolAmm) -> u32 {
+    let custodies = amm.custodies.len() as u32;
+
+    let base = match operation {
+        Operation::Swap => SWAP_BASE_COMPUTE_UNITS,
+        Operation::AddLiquidity => ADD_LIQUIDITY_BASE_COMPUTE_UNITS,
+        Operation::RemoveLiquidity => REMOVE_LIQUIDITY_BASE_COMPUTE_UNITS,
+    };
+
+    let token_2022_transfers = if amm.mints.values().any(|m| m.program_id == TOKEN_2022_ID) {
+        2
+    } else {
+        0
+    };
+
+    let estimate = base
+        + custodies * PER_CUSTODY_COMPUTE_UNITS
+        + token_2022_transfers * TOKEN_2022_TRANSFER_COMPUTE_UNITS;
+
+    (estimate * (100 + MARGIN_PCT) / 100).min(MAX_COMPUTE_UNITS)
+}
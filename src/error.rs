@@ -0,0 +1,39 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+/// Raised by `update` when an account handed over in the `AccountMap` doesn't
+/// look like the account it is supposed to be. Callers can recover it from the
+/// returned `anyhow::Error` with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AccountValidationError {
+    #[error("Account {account} is owned by {actual}, expected {expected}")]
+    InvalidOwner {
+        account: Pubkey,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    #[error("Account {account} is owned by {actual}, which is not a known oracle program")]
+    InvalidOracleOwner { account: Pubkey, actual: Pubkey },
+    #[error("Mint {account} is owned by {actual}, which is not a token program")]
+    InvalidTokenProgram { account: Pubkey, actual: Pubkey },
+    #[error("Account {account} has {actual} bytes, expected at least {expected}")]
+    InvalidSize {
+        account: Pubkey,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Account {account} has an invalid discriminator")]
+    InvalidDiscriminator { account: Pubkey },
+    #[error("Custody {custody} belongs to pool {actual}, expected {expected}")]
+    CustodyPoolMismatch {
+        custody: Pubkey,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    #[error("Position {position} belongs to pool {actual}, expected {expected}")]
+    PositionPoolMismatch {
+        position: Pubkey,
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+}
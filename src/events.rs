@@ -0,0 +1,216 @@
+use adrena::{
+    events::{AddLiquidityEvent, RemoveLiquidityEvent, SwapEvent},
+    instruction,
+};
+use anchor_lang::{prelude::*, Discriminator};
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose, Engine};
+use jupiter_amm_interface::Quote;
+use num_traits::FromPrimitive;
+use rust_decimal::Decimal;
+
+use crate::Operation;
+
+/// What an executed swap, ALP mint or ALP redeem actually did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionRecord {
+    pub operation: Operation,
+    pub owner: Pubkey,
+    /// Custody paid into, `None` when ALP was redeemed.
+    pub custody_in: Option<Pubkey>,
+    /// Custody paid out of, `None` when ALP was minted.
+    pub custody_out: Option<Pubkey>,
+    pub in_amount: u64,
+    pub out_amount: u64,
+    /// Fees taken on the way in and out, in the token they were taken in.
+    pub fee_in: u64,
+    pub fee_out: u64,
+    pub price_in: Option<u64>,
+    pub price_out: Option<u64>,
+}
+
+/// Arguments of an Adrena instruction, decoded from its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionArgs {
+    Swap {
+        amount_in: u64,
+        min_amount_out: u64,
+    },
+    AddLiquidity {
+        amount_in: u64,
+        min_lp_amount_out: u64,
+    },
+    RemoveLiquidity {
+        lp_amount_in: u64,
+        min_amount_out: u64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct QuoteDiff {
+    pub quoted_in_amount: u64,
+    pub executed_in_amount: u64,
+    pub quoted_out_amount: u64,
+    pub executed_out_amount: u64,
+    /// Executed minus quoted.
+    pub out_amount_diff: i128,
+    pub out_amount_diff_pct: Decimal,
+}
+
+impl ExecutionRecord {
+    pub fn diff(&self, quote: &Quote) -> anyhow::Result<QuoteDiff> {
+        let out_amount_diff = self.out_amount as i128 - quote.out_amount as i128;
+
+        let out_amount_diff_pct = if quote.out_amount == 0 {
+            Decimal::ZERO
+        } else {
+            Decimal::ONE_HUNDRED
+                .checked_mul(Decimal::from_i128(out_amount_diff).context("Can't convert diff")?)
+                .and_then(|per| per.checked_div(Decimal::from(quote.out_amount)))
+                .context("Can't calculate diff percentage")?
+        };
+
+        Ok(QuoteDiff {
+            quoted_in_amount: quote.in_amount,
+            executed_in_amount: self.in_amount,
+            quoted_out_amount: quote.out_amount,
+            executed_out_amount: self.out_amount,
+            out_amount_diff,
+            out_amount_diff_pct,
+        })
+    }
+}
+
+/// Extracts the swap and liquidity events `program_id` emitted from a
+/// transaction's log messages, in execution order. Events logged by other
+/// programs, e.g. the router that invoked Adrena, are skipped, and a failed
+/// transaction has none.
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> anyhow::Result<Vec<ExecutionRecord>> {
+    let program_id = program_id.to_string();
+    let mut invocations: Vec<&str> = vec![];
+    let mut records = vec![];
+
+    for log in logs {
+        let mut words = log.split_whitespace();
+
+        match (words.next(), words.next(), words.next()) {
+            (Some("Program"), Some(program), Some("invoke")) => invocations.push(program),
+            (Some("Program"), Some(_), Some("success")) => {
+                invocations.pop();
+            }
+            // A failed invocation reverts the whole transaction
+            (Some("Program"), Some(_), Some("failed:")) => return Ok(vec![]),
+            (Some("Program"), Some("data:"), Some(data))
+                if invocations.last() == Some(&program_id.as_str()) =>
+            {
+                let data = general_purpose::STANDARD
+                    .decode(data)
+                    .context(format!("Invalid event data: {data}"))?;
+
+                if let Some(record) = decode_event(&data)? {
+                    records.push(record);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(records)
+}
+
+/// Decodes swap and liquidity events, `None` for any other event.
+pub fn decode_event(data: &[u8]) -> anyhow::Result<Option<ExecutionRecord>> {
+    if data.len() < 8 {
+        return Err(anyhow!("Event data is shorter than a discriminator"));
+    }
+
+    let (discriminator, mut payload) = data.split_at(8);
+
+    let record = if discriminator == SwapEvent::DISCRIMINATOR {
+        let event = SwapEvent::deserialize(&mut payload)?;
+
+        ExecutionRecord {
+            operation: Operation::Swap,
+            owner: event.owner,
+            custody_in: Some(event.custody_in),
+            custody_out: Some(event.custody_out),
+            in_amount: event.amount_in,
+            out_amount: event.amount_out,
+            fee_in: event.fee_in,
+            fee_out: event.fee_out,
+            price_in: Some(event.price_in),
+            price_out: Some(event.price_out),
+        }
+    } else if discriminator == AddLiquidityEvent::DISCRIMINATOR {
+        let event = AddLiquidityEvent::deserialize(&mut payload)?;
+
+        ExecutionRecord {
+            operation: Operation::AddLiquidity,
+            owner: event.owner,
+            custody_in: Some(event.custody),
+            custody_out: None,
+            in_amount: event.amount_in,
+            out_amount: event.lp_amount_out,
+            fee_in: event.fee,
+            fee_out: 0,
+            price_in: Some(event.token_price),
+            price_out: None,
+        }
+    } else if discriminator == RemoveLiquidityEvent::DISCRIMINATOR {
+        let event = RemoveLiquidityEvent::deserialize(&mut payload)?;
+
+        ExecutionRecord {
+            operation: Operation::RemoveLiquidity,
+            owner: event.owner,
+            custody_in: None,
+            custody_out: Some(event.custody),
+            in_amount: event.lp_amount_in,
+            out_amount: event.amount_out,
+            fee_in: 0,
+            fee_out: event.fee,
+            price_in: None,
+            price_out: Some(event.token_price),
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(record))
+}
+
+/// Decodes the data of a swap or liquidity instruction, `None` for any other
+/// Adrena instruction.
+pub fn decode_instruction(data: &[u8]) -> anyhow::Result<Option<InstructionArgs>> {
+    if data.len() < 8 {
+        return Err(anyhow!("Instruction data is shorter than a discriminator"));
+    }
+
+    let (discriminator, mut args) = data.split_at(8);
+
+    let args = if discriminator == instruction::Swap::DISCRIMINATOR {
+        let params = instruction::Swap::deserialize(&mut args)?.params;
+
+        InstructionArgs::Swap {
+            amount_in: params.amount_in,
+            min_amount_out: params.min_amount_out,
+        }
+    } else if discriminator == instruction::AddLiquidity::DISCRIMINATOR {
+        let params = instruction::AddLiquidity::deserialize(&mut args)?.params;
+
+        InstructionArgs::AddLiquidity {
+            amount_in: params.amount_in,
+            min_lp_amount_out: params.min_lp_amount_out,
+        }
+    } else if discriminator == instruction::RemoveLiquidity::DISCRIMINATOR {
+        let params = instruction::RemoveLiquidity::deserialize(&mut args)?.params;
+
+        InstructionArgs::RemoveLiquidity {
+            lp_amount_in: params.lp_amount_in,
+            min_amount_out: params.min_amount_out,
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(args))
+}
@@ -0,0 +1,113 @@
+use adrena::state::cortex::Cortex;
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{Amm, QuoteParams};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{Operation, PoolAmm, BPS_POWER, FEE_REDISTRIBUTION_MINT};
+
+/// How Adrena redistributes fees, in BPS of the total, as set in the Cortex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSplit {
+    pub lp_staking_bps: u64,
+    pub lm_staking_bps: u64,
+    pub protocol_bps: u64,
+    /// Paid out of the protocol share when the trader has a referrer.
+    pub referrer_bps: u64,
+}
+
+impl FeeSplit {
+    pub(crate) fn from_cortex(cortex: &Cortex) -> Self {
+        FeeSplit {
+            lp_staking_bps: cortex.lp_staking_fee_share_bps as u64,
+            lm_staking_bps: cortex.lm_staking_fee_share_bps as u64,
+            protocol_bps: cortex.protocol_fee_share_bps as u64,
+            referrer_bps: cortex.referrer_fee_share_bps as u64,
+        }
+    }
+}
+
+/// Fees of one quote, in the reward token, as they end up in each vault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeAttribution {
+    pub fee_mint: Pubkey,
+    pub total_amount: u64,
+    pub total_usd: u64,
+    pub lp_staking_amount: u64,
+    pub lm_staking_amount: u64,
+    pub protocol_amount: u64,
+    pub referrer_amount: u64,
+}
+
+impl PoolAmm {
+    /// Fee split read from the Cortex on the last update.
+    pub fn fee_split(&self) -> Option<&FeeSplit> {
+        self.fee_split.as_ref()
+    }
+
+    /// Quotes `quote_params` for `owner` and splits its fees between LP
+    /// staking, LM staking, the protocol and the referrer of `owner`'s profile,
+    /// if it was loaded and has one.
+    pub fn attribute_fees(
+        &self,
+        quote_params: &QuoteParams,
+        owner: Option<&Pubkey>,
+    ) -> anyhow::Result<FeeAttribution> {
+        let split = self.fee_split.as_ref().context("Cortex not loaded")?;
+
+        if split.lp_staking_bps + split.lm_staking_bps + split.protocol_bps != BPS_POWER {
+            return Err(anyhow!("Fee split doesn't add up to {BPS_POWER} BPS"));
+        }
+
+        if split.referrer_bps > split.protocol_bps {
+            return Err(anyhow!("Referrer share exceeds the protocol share"));
+        }
+
+        let user_profile = owner.and_then(|owner| self.user_profile(owner));
+        let has_referrer = user_profile.is_some_and(|profile| profile.referrer.is_some());
+
+        let quote = self.quote_with_profile(quote_params, user_profile)?;
+        let (_, fees_custody, fees_price) = self.get_custody_and_oracle(FEE_REDISTRIBUTION_MINT)?;
+
+        // Swaps report fees in the reward token, ALP mints in USD and ALP
+        // redeems in the redeemed token.
+        let total_usd = match self
+            .get_operation(&quote_params.input_mint, &quote_params.output_mint)
+        {
+            Operation::Swap => {
+                fees_price.get_asset_amount_usd(quote.fee_amount, fees_custody.decimals)?
+            }
+            Operation::AddLiquidity => quote.fee_amount,
+            Operation::RemoveLiquidity => {
+                let (_, custody, price) = self.get_custody_and_oracle(quote_params.output_mint)?;
+                price.get_asset_amount_usd(quote.fee_amount, custody.decimals)?
+            }
+        };
+        let total_amount = fees_price.get_token_amount(total_usd, fees_custody.decimals)?;
+
+        let share = |bps: u64| -> anyhow::Result<u64> {
+            u64::try_from(total_amount as u128 * bps as u128 / BPS_POWER as u128)
+                .context("Can't split fees")
+        };
+
+        let lp_staking_amount = share(split.lp_staking_bps)?;
+        let lm_staking_amount = share(split.lm_staking_bps)?;
+        let referrer_amount = if has_referrer {
+            share(split.referrer_bps)?
+        } else {
+            0
+        };
+
+        Ok(FeeAttribution {
+            fee_mint: FEE_REDISTRIBUTION_MINT,
+            total_amount,
+            total_usd,
+            lp_staking_amount,
+            lm_staking_amount,
+            // Rounding dust stays with the protocol
+            protocol_amount: total_amount - lp_staking_amount - lm_staking_amount - referrer_amount,
+            referrer_amount,
+        })
+    }
+}
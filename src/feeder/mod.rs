@@ -0,0 +1,257 @@
+mod pubsub;
+
+pub use pubsub::*;
+
+use std::{cell::RefCell, collections::HashSet, time::Duration};
+
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{AccountMap, Amm, QuoteParams};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{AccountSource, PoolAmm, SharedPoolAmm, BPS_POWER};
+
+/// Wait before reconnecting a lost subscription.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Changes of the accounts subscribed to on a connection. Ends, or yields an
+/// error, when the connection is lost.
+pub type AccountUpdates = mpsc::UnboundedReceiver<anyhow::Result<(Pubkey, Account)>>;
+
+/// Pushes account changes, e.g. a websocket `accountSubscribe` or a Geyser plugin.
+pub trait AccountSubscriber {
+    type Connection: AccountConnection;
+
+    /// Opens a connection, streaming the changes of the accounts subscribed to
+    /// through it until the updates receiver is dropped.
+    fn connect(&self) -> anyhow::Result<(Self::Connection, AccountUpdates)>;
+}
+
+/// Accounts streamed by a connection, which can change while it's open.
+pub trait AccountConnection {
+    fn subscribe(&mut self, key: Pubkey) -> anyhow::Result<()>;
+
+    fn unsubscribe(&mut self, key: Pubkey) -> anyhow::Result<()>;
+}
+
+/// A probe quote moved by more than the feeder's threshold. `None` means the
+/// quote failed, e.g. a stale oracle or a disabled route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuoteChange {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount: u64,
+    pub previous_out_amount: Option<u64>,
+    pub out_amount: Option<u64>,
+}
+
+/// Keeps a shared `PoolAmm` up to date from pushed account changes instead of
+/// polling `get_accounts_to_update`.
+pub struct PoolFeeder {
+    amm: SharedPoolAmm,
+    accounts: CachedAccountSource,
+    probes: Vec<QuoteParams>,
+    out_amounts: Vec<Option<u64>>,
+    threshold_bps: u64,
+    changes: broadcast::Sender<QuoteChange>,
+}
+
+impl PoolFeeder {
+    /// Loads the pool from `source`, which is also used to fetch accounts that
+    /// show up later, e.g. a custody added to the pool.
+    ///
+    /// Listeners are notified when the out amount of one of the `probes` moves
+    /// by more than `threshold_bps` since the last notification.
+    pub fn new(
+        source: Box<dyn AccountSource + Send>,
+        pool_key: Pubkey,
+        params: Option<serde_json::Value>,
+        probes: Vec<QuoteParams>,
+        threshold_bps: u64,
+    ) -> anyhow::Result<Self> {
+        let accounts = CachedAccountSource {
+            accounts: RefCell::default(),
+            source,
+        };
+
+        let amm = PoolAmm::load(&accounts, pool_key, params)?;
+        accounts.get_accounts(&amm.subscription_keys())?;
+
+        let out_amounts = probes.iter().map(|probe| out_amount(&amm, probe)).collect();
+        let (changes, _) = broadcast::channel(1024);
+
+        Ok(PoolFeeder {
+            amm: SharedPoolAmm::new(amm),
+            accounts,
+            probes,
+            out_amounts,
+            threshold_bps,
+            changes,
+        })
+    }
+
+    /// The quoter kept up to date by `run`.
+    pub fn amm(&self) -> SharedPoolAmm {
+        self.amm.clone()
+    }
+
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<QuoteChange> {
+        self.changes.subscribe()
+    }
+
+    /// Applies account changes, only returning when `subscriber` can't connect.
+    ///
+    /// An update the pool can't be refreshed with is logged and skipped, the
+    /// last good state is kept. A lost connection is reopened after
+    /// `RECONNECT_DELAY`, and the accounts reloaded from the source since
+    /// changes were missed in between. When the pool's accounts change, e.g.
+    /// when a custody is added, only the keys that came or went are subscribed
+    /// to or unsubscribed from.
+    pub async fn run(mut self, subscriber: impl AccountSubscriber) -> anyhow::Result<()> {
+        loop {
+            let (mut connection, mut updates) = subscriber.connect()?;
+
+            if let Err(err) = self.follow(&mut connection, &mut updates).await {
+                log::warn!("Account subscription lost, reconnecting: {err:#}");
+            }
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+
+            if let Err(err) = self.reload() {
+                log::warn!("{err:#}");
+            }
+        }
+    }
+
+    /// Applies the changes of a connection, only returning once it's lost.
+    async fn follow(
+        &mut self,
+        connection: &mut impl AccountConnection,
+        updates: &mut AccountUpdates,
+    ) -> anyhow::Result<()> {
+        let mut subscribed = HashSet::new();
+
+        loop {
+            let keys: HashSet<Pubkey> =
+                self.amm.current().subscription_keys().into_iter().collect();
+
+            for key in keys.difference(&subscribed) {
+                connection.subscribe(*key)?;
+            }
+            for key in subscribed.difference(&keys) {
+                connection.unsubscribe(*key)?;
+            }
+            subscribed = keys;
+
+            let (key, account) = updates
+                .recv()
+                .await
+                .ok_or(anyhow!("Account subscription closed"))??;
+
+            if let Err(err) = self.apply(AccountMap::from([(key, account)])) {
+                log::warn!("Skipped the update of {key}: {err:#}");
+            }
+        }
+    }
+
+    /// Reloads every subscribed account from the source.
+    fn reload(&mut self) -> anyhow::Result<()> {
+        let keys = self.amm.current().subscription_keys();
+        let accounts = self
+            .accounts
+            .source
+            .get_accounts(&keys)
+            .context("Can't reload the pool's accounts")?;
+
+        self.apply(accounts)
+            .context("Can't apply the reloaded accounts")
+    }
+
+    /// Refreshes the pool with `accounts`, leaving both the pool and the cached
+    /// accounts as they were when it fails.
+    fn apply(&mut self, accounts: AccountMap) -> anyhow::Result<()> {
+        let previous: Vec<(Pubkey, Option<Account>)> = {
+            let mut cached = self.accounts.accounts.borrow_mut();
+            accounts
+                .into_iter()
+                .map(|(key, account)| (key, cached.insert(key, account)))
+                .collect()
+        };
+
+        let mut amm = PoolAmm::clone(&self.amm.current());
+        if let Err(err) = amm.refresh(&self.accounts) {
+            let mut cached = self.accounts.accounts.borrow_mut();
+            for (key, account) in previous {
+                match account {
+                    Some(account) => cached.insert(key, account),
+                    None => cached.remove(&key),
+                };
+            }
+
+            return Err(err);
+        }
+
+        for (probe, previous_out_amount) in self.probes.iter().zip(self.out_amounts.iter_mut()) {
+            let out_amount = out_amount(&amm, probe);
+
+            if !exceeds_threshold(*previous_out_amount, out_amount, self.threshold_bps) {
+                continue;
+            }
+
+            // No listeners is fine, changes are only a notification
+            let _ = self.changes.send(QuoteChange {
+                input_mint: probe.input_mint,
+                output_mint: probe.output_mint,
+                amount: probe.amount,
+                previous_out_amount: *previous_out_amount,
+                out_amount,
+            });
+            *previous_out_amount = out_amount;
+        }
+
+        self.amm.store(amm);
+
+        Ok(())
+    }
+}
+
+/// Latest known accounts, falling back to `source` for accounts never seen.
+struct CachedAccountSource {
+    accounts: RefCell<AccountMap>,
+    source: Box<dyn AccountSource + Send>,
+}
+
+impl AccountSource for CachedAccountSource {
+    fn get_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<AccountMap> {
+        let missing: Vec<Pubkey> = keys
+            .iter()
+            .filter(|key| !self.accounts.borrow().contains_key(key))
+            .copied()
+            .collect();
+
+        if !missing.is_empty() {
+            let fetched = self.source.get_accounts(&missing)?;
+            self.accounts.borrow_mut().extend(fetched);
+        }
+
+        let accounts = self.accounts.borrow();
+        Ok(keys
+            .iter()
+            .filter_map(|key| Some((*key, accounts.get(key)?.clone())))
+            .collect())
+    }
+}
+
+fn out_amount(amm: &PoolAmm, probe: &QuoteParams) -> Option<u64> {
+    amm.quote(probe).ok().map(|quote| quote.out_amount)
+}
+
+fn exceeds_threshold(previous: Option<u64>, current: Option<u64>, threshold_bps: u64) -> bool {
+    match (previous, current) {
+        (Some(previous), Some(current)) => {
+            let delta = previous.abs_diff(current) as u128 * BPS_POWER as u128;
+            delta > previous as u128 * threshold_bps as u128
+        }
+        (previous, current) => previous != current,
+    }
+}
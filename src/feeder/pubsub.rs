@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use futures::{
+    stream::{self, SelectAll},
+    StreamExt,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use tokio::sync::mpsc;
+
+use super::{AccountConnection, AccountSubscriber, AccountUpdates};
+
+/// Subscribes through the websocket `accountSubscribe` method, one websocket
+/// per connection. Must be used from within a tokio runtime.
+#[derive(Clone, Debug)]
+pub struct PubsubAccountSubscriber {
+    pub url: String,
+    pub commitment: CommitmentConfig,
+}
+
+impl PubsubAccountSubscriber {
+    pub fn new(url: impl ToString) -> Self {
+        PubsubAccountSubscriber {
+            url: url.to_string(),
+            commitment: CommitmentConfig::confirmed(),
+        }
+    }
+}
+
+/// Sends subscription changes to the task holding the websocket.
+#[derive(Debug)]
+pub struct PubsubConnection {
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+#[derive(Debug)]
+enum Request {
+    Subscribe(Pubkey),
+    Unsubscribe(Pubkey),
+}
+
+impl AccountSubscriber for PubsubAccountSubscriber {
+    type Connection = PubsubConnection;
+
+    fn connect(&self) -> anyhow::Result<(PubsubConnection, AccountUpdates)> {
+        let (requests, request_receiver) = mpsc::unbounded_channel();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let subscriber = self.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = subscriber.forward(request_receiver, &sender).await {
+                let _ = sender.send(Err(err));
+            }
+        });
+
+        Ok((PubsubConnection { requests }, receiver))
+    }
+}
+
+impl AccountConnection for PubsubConnection {
+    fn subscribe(&mut self, key: Pubkey) -> anyhow::Result<()> {
+        self.requests
+            .send(Request::Subscribe(key))
+            .map_err(|_| anyhow!("Websocket closed"))
+    }
+
+    fn unsubscribe(&mut self, key: Pubkey) -> anyhow::Result<()> {
+        self.requests
+            .send(Request::Unsubscribe(key))
+            .map_err(|_| anyhow!("Websocket closed"))
+    }
+}
+
+impl PubsubAccountSubscriber {
+    async fn forward(
+        &self,
+        mut requests: mpsc::UnboundedReceiver<Request>,
+        sender: &mpsc::UnboundedSender<anyhow::Result<(Pubkey, Account)>>,
+    ) -> anyhow::Result<()> {
+        let client = PubsubClient::new(&self.url)
+            .await
+            .context(format!("Can't connect to {}", self.url))?;
+
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.commitment),
+            ..Default::default()
+        };
+
+        // Every stream ends with `None`, telling a subscription closed by the
+        // server from one dropped by `Unsubscribe` through its id
+        let mut updates = SelectAll::new();
+        let mut unsubscribes = HashMap::new();
+        let mut next_id = 0u64;
+
+        loop {
+            tokio::select! {
+                request = requests.recv() => match request {
+                    Some(Request::Subscribe(key)) => {
+                        let (stream, unsubscribe) = client
+                            .account_subscribe(&key, Some(config.clone()))
+                            .await
+                            .context(format!("Can't subscribe to {key}"))?;
+
+                        let id = next_id;
+                        next_id += 1;
+
+                        updates.push(
+                            stream
+                                .map(|response| Some(response.value))
+                                .chain(stream::once(async { None }))
+                                .map(move |account| (key, id, account))
+                                .boxed(),
+                        );
+                        unsubscribes.insert(key, (id, unsubscribe));
+                    }
+                    Some(Request::Unsubscribe(key)) => {
+                        if let Some((_, unsubscribe)) = unsubscribes.remove(&key) {
+                            unsubscribe().await;
+                        }
+                    }
+                    // The feeder dropped the connection
+                    None => return Ok(()),
+                },
+                Some((key, id, account)) = updates.next(), if !updates.is_empty() => {
+                    let Some(account) = account else {
+                        if unsubscribes.get(&key).map(|(current, _)| *current) == Some(id) {
+                            return Err(anyhow!("Subscription to {key} closed"));
+                        }
+                        continue;
+                    };
+
+                    let account = account
+                        .decode::<Account>()
+                        .ok_or(anyhow!("Can't decode the update of {key}"))?;
+
+                    if sender.send(Ok((key, account))).is_err() {
+                        // The feeder dropped the receiver
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
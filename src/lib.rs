@@ -1,32 +1,104 @@
+mod account_source;
+mod arbitrage;
+mod aum;
+mod backtest;
+mod borrow_rate;
+mod compute_units;
+mod error;
+mod events;
+mod fee_split;
+#[cfg(feature = "stream")]
+mod feeder;
+mod lookup_table;
+mod lp;
+mod native_sol;
+pub mod oracle;
+mod perps;
+mod position;
 mod quote;
-
-use adrena::state::{custody::Custody, oracle::OraclePrice, pool::Pool};
+mod route_config;
+mod scenario;
+mod shared;
+mod snapshot;
+mod token;
+mod token_accounts;
+mod user_profile;
+mod utils;
+mod validation;
+
+pub use account_source::*;
+pub use arbitrage::*;
+pub use aum::AumDrift;
+pub use backtest::*;
+pub use borrow_rate::*;
+pub use compute_units::*;
+pub use error::AccountValidationError;
+pub use events::*;
+pub use fee_split::*;
+#[cfg(feature = "stream")]
+pub use feeder::*;
+pub use lp::*;
+pub use native_sol::*;
+pub use perps::*;
+pub use position::PositionHealth;
+pub use route_config::RouteConfig;
+pub use scenario::*;
+pub use shared::SharedPoolAmm;
+pub use snapshot::*;
+pub use token::TokenMint;
+pub use user_profile::UserProfileState;
+
+use adrena::state::{cortex::Cortex, custody::Custody, oracle::OraclePrice, pool::Pool};
 use anchor_lang::{system_program, AccountDeserialize};
-use anyhow::Context;
-use jupiter_amm_interface::{try_get_account_data, Amm, AmmContext, Quote, SwapAndAccountMetas};
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{Amm, AmmContext, ClockRef, Quote, SwapAndAccountMetas};
 use num_traits::FromPrimitive;
+use oracle::parse_oracle_account;
 use quote::{
     calculate_add_liquidity, calculate_remove_liquidity, calculate_swap, get_add_liquidity_metas,
-    get_remove_liquidity_metas, get_swap_metas, ComputeResult,
+    get_remove_liquidity_metas, get_swap_metas, ComputeResult, ADD_LIQUIDITY_ACCOUNTS_LEN,
+    REMOVE_LIQUIDITY_ACCOUNTS_LEN, SWAP_ACCOUNTS_LEN,
 };
 use rust_decimal::Decimal;
-use solana_sdk::{account_info::IntoAccountInfo, pubkey as key, pubkey::Pubkey};
-use spl_token::{solana_program::program_pack::Pack, state::Mint};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey as key, pubkey::Pubkey};
+use spl_token_2022::state::Mint;
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering, Arc},
+};
+use validation::{get_account, validate_program_account};
 
 const SPL_TOKEN_ID: Pubkey = key!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_ID: Pubkey = key!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 
 const PROTOCOL_FEE_RECIPIENT: Pubkey = key!("5STGJRnjLKbssEkk5AmKpqebPLt5yk71RMFmGtxWwjgG");
 const FEE_REDISTRIBUTION_MINT: Pubkey = key!("3jdYcGYZaQVvcvMQGqVpt37JegEoDDnX7k4gSGAeGRqG");
 const REWARD_ORACLE_ACCOUNT: Pubkey = key!("5SSkXsEKQepHHAewytPVwdej4epN1nxgLVM84L4KXgy7");
 const LM_STAKING: Pubkey = key!("AUP8PVY9gC5VGmTdyZLVB2DskLeScKGxY5VeZtZN7hFR");
 
-#[derive(Clone, Debug)]
+const USD_DECIMALS: u32 = 6;
+const PRICE_DECIMALS: u32 = 10;
+const BPS_POWER: u64 = 10_000;
+/// Borrow rates and cumulative interest.
+const RATE_DECIMALS: u32 = 9;
+/// Borrow rates are hourly.
+const SECONDS_PER_HOUR: u64 = 3_600;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum UpdateType {
     Custodies,
     OraclesAndTokens,
 }
 
+/// Adrena instruction a route between two mints goes through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Swap,
+    AddLiquidity,
+    RemoveLiquidity,
+}
+
 pub struct CalculateFeesParams<'a> {
     in_oracle: &'a OraclePrice,
     in_decimals: u8,
@@ -37,15 +109,37 @@ pub struct CalculateFeesParams<'a> {
     fees: (u64, u64),
 }
 
+/// Account state is kept behind `Arc`s that `update` replaces rather than
+/// mutates, so cloning is O(1) and clones never observe a half applied update.
 #[derive(Clone)]
 pub struct PoolAmm {
     pool_key: Pubkey,
-    pool: Pool,
-    custodies: HashMap<Pubkey, Custody>,
-    oracle_prices: HashMap<Pubkey, OraclePrice>,
+    pool: Arc<Pool>,
+    /// AUM used to price ALP, recomputed from custodies and oracle prices on every
+    /// oracle update. Falls back to `pool.aum_usd` until the first one, and when
+    /// it can't be recomputed.
+    aum_usd: u128,
+    /// Why the live AUM couldn't be recomputed at the last oracle update, in
+    /// which case `aum_usd` is the stored one.
+    aum_error: Option<String>,
+    custodies: Arc<HashMap<Pubkey, Custody>>,
+    oracle_prices: Arc<HashMap<Pubkey, OraclePrice>>,
     lp_token_mint: (Pubkey, Option<Mint>),
+    /// Custody and LP mints, with the token program owning each of them.
+    mints: Arc<HashMap<Pubkey, TokenMint>>,
     program_id: Pubkey,
     update_type: UpdateType,
+    clock_ref: ClockRef,
+    /// Owner of each oracle account, i.e. the oracle program it comes from.
+    oracle_programs: Arc<HashMap<Pubkey, Pubkey>>,
+    route_config: Arc<RouteConfig>,
+    /// Profiles loaded with `load_user_profile` or for `quoting_owner`, keyed by
+    /// owner and refreshed on every update. `None` when the owner has no profile.
+    user_profiles: Arc<HashMap<Pubkey, Option<UserProfileState>>>,
+    /// Owner whose fee discount `quote` applies.
+    quoting_owner: Option<Pubkey>,
+    /// Fee redistribution read from the Cortex, `None` until the first update.
+    fee_split: Option<FeeSplit>,
 }
 
 impl PoolAmm {
@@ -53,6 +147,86 @@ impl PoolAmm {
         Pubkey::find_program_address(seeds, &self.program_id).0
     }
 
+    fn cortex_key(&self) -> Pubkey {
+        self.pda(&[b"cortex"])
+    }
+
+    pub fn get_operation(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> Operation {
+        if self.lp_token_mint.0 == *input_mint {
+            Operation::RemoveLiquidity
+        } else if self.lp_token_mint.0 == *output_mint {
+            Operation::AddLiquidity
+        } else {
+            Operation::Swap
+        }
+    }
+
+    /// Number of accounts in the instruction built for `operation`.
+    pub fn get_accounts_len_for(&self, operation: Operation) -> usize {
+        match operation {
+            Operation::Swap => SWAP_ACCOUNTS_LEN,
+            Operation::AddLiquidity => ADD_LIQUIDITY_ACCOUNTS_LEN,
+            Operation::RemoveLiquidity => REMOVE_LIQUIDITY_ACCOUNTS_LEN,
+        }
+    }
+
+    fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.mints
+            .get(mint)
+            .map(|m| m.program_id)
+            .unwrap_or(SPL_TOKEN_ID)
+    }
+
+    /// Adrena instructions take a single token program, so every mint they move,
+    /// the fee redistribution mint included, must be owned by the same one.
+    fn instruction_token_program(&self, mints: &[Pubkey]) -> anyhow::Result<Pubkey> {
+        let token_program = self.token_program(&mints[0]);
+
+        if let Some(mint) = mints
+            .iter()
+            .find(|m| self.token_program(m) != token_program)
+        {
+            return Err(anyhow!(
+                "Mint {mint} is not owned by {token_program}, Adrena can't route between token programs"
+            ));
+        }
+
+        Ok(token_program)
+    }
+
+    /// Fee withheld by Token-2022 when transferring `amount` of `mint`.
+    fn transfer_fee(&self, mint: &Pubkey, amount: u64) -> anyhow::Result<u64> {
+        match self.mints.get(mint) {
+            Some(token_mint) => {
+                token_mint.transfer_fee(self.clock_ref.epoch.load(Ordering::Relaxed), amount)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Returns the custody's token account, making sure it is the vault PDA owned
+    /// by the custody rather than whatever the custody state points to.
+    fn get_custody_token_account(
+        &self,
+        custody_key: &Pubkey,
+        custody: &Custody,
+    ) -> anyhow::Result<Pubkey> {
+        let custody_token_account = self.pda(&[
+            b"custody_token_account",
+            self.pool_key.as_ref(),
+            custody.mint.as_ref(),
+        ]);
+
+        if custody.token_account != custody_token_account {
+            return Err(anyhow!(
+                "Custody {custody_key} token account {} is not the custody vault {custody_token_account}",
+                custody.token_account
+            ));
+        }
+
+        Ok(custody_token_account)
+    }
+
     fn get_custody_and_oracle(
         &self,
         mint: Pubkey,
@@ -75,6 +249,39 @@ impl PoolAmm {
         Ok((custody_key, custody, oracle_price))
     }
 
+    fn quote_with_profile(
+        &self,
+        quote_params: &jupiter_amm_interface::QuoteParams,
+        user_profile: Option<&UserProfileState>,
+    ) -> anyhow::Result<Quote> {
+        self.check_route_allowed(&quote_params.input_mint, &quote_params.output_mint)?;
+
+        let ComputeResult {
+            in_amount,
+            out_amount,
+            fee_amount,
+            fee_pct,
+        } = match self.get_operation(&quote_params.input_mint, &quote_params.output_mint) {
+            Operation::RemoveLiquidity => self
+                .ensure_live_aum()
+                .and_then(|_| calculate_remove_liquidity(self, quote_params)),
+            Operation::AddLiquidity => self
+                .ensure_live_aum()
+                .and_then(|_| calculate_add_liquidity(self, quote_params)),
+            Operation::Swap => calculate_swap(self, quote_params, user_profile),
+        }?;
+
+        Ok(Quote {
+            min_in_amount: None,
+            min_out_amount: None,
+            in_amount,
+            out_amount,
+            fee_amount,
+            fee_mint: FEE_REDISTRIBUTION_MINT,
+            fee_pct,
+        })
+    }
+
     fn calculate_swap_fees(
         &self,
         CalculateFeesParams {
@@ -115,10 +322,13 @@ impl PoolAmm {
 impl Amm for PoolAmm {
     fn from_keyed_account(
         keyed_account: &jupiter_amm_interface::KeyedAccount,
-        _amm_context: &AmmContext,
+        amm_context: &AmmContext,
     ) -> anyhow::Result<Self> {
-        let program_id = keyed_account.account.owner;
+        let program_id = adrena::ID;
         let pool_key = keyed_account.key;
+
+        validate_program_account::<Pool>(&pool_key, &keyed_account.account, &program_id)?;
+
         let pool = Pool::try_deserialize(&mut &keyed_account.account.data[..])?;
         let lp_token_mint = Pubkey::create_program_address(
             &[b"lp_token_mint", pool_key.as_ref(), &[pool.lp_token_bump]],
@@ -128,11 +338,20 @@ impl Amm for PoolAmm {
         Ok(PoolAmm {
             pool_key: keyed_account.key,
             program_id,
-            pool,
-            custodies: HashMap::new(),
-            oracle_prices: HashMap::new(),
+            aum_usd: pool.aum_usd.to_u128(),
+            aum_error: None,
+            pool: Arc::new(pool),
+            custodies: Arc::default(),
+            oracle_prices: Arc::default(),
             lp_token_mint: (lp_token_mint, None),
+            mints: Arc::default(),
             update_type: UpdateType::Custodies,
+            clock_ref: amm_context.clock_ref.clone(),
+            oracle_programs: Arc::default(),
+            route_config: Arc::new(RouteConfig::from_params(keyed_account.params.as_ref())?),
+            user_profiles: Arc::default(),
+            quoting_owner: None,
+            fee_split: None,
         })
     }
 
@@ -153,9 +372,25 @@ impl Amm for PoolAmm {
     }
 
     fn get_reserve_mints(&self) -> Vec<Pubkey> {
-        // Should all custodies be considered reserves? (Stable too?)
-        // Add ALP mint/redeem which is not a custody
-        self.custodies.values().map(|c| c.mint).collect()
+        let mints: Vec<Pubkey> = self
+            .custodies
+            .values()
+            .map(|c| c.mint)
+            .chain(std::iter::once(self.lp_token_mint.0))
+            .collect();
+
+        // Only keep mints that can be traded in at least one direction
+        mints
+            .iter()
+            .filter(|mint| {
+                mints.iter().any(|other| {
+                    other != *mint
+                        && (self.is_route_allowed(mint, other)
+                            || self.is_route_allowed(other, mint))
+                })
+            })
+            .copied()
+            .collect()
     }
 
     fn get_accounts_to_update(&self) -> Vec<Pubkey> {
@@ -171,12 +406,18 @@ impl Amm for PoolAmm {
                         .filter(|acc| *acc != system_program::ID)
                         .collect(),
                 );
+                keys.push(self.cortex_key());
+                keys.extend(
+                    self.tracked_profile_owners()
+                        .iter()
+                        .map(|owner| self.get_user_profile_key(owner)),
+                );
                 keys
             }
             UpdateType::OraclesAndTokens => self
                 .custodies
                 .values()
-                .map(|c| c.oracle.oracle_account)
+                .flat_map(|c| [c.oracle.oracle_account, c.mint])
                 .collect(),
         }
     }
@@ -189,42 +430,97 @@ impl Amm for PoolAmm {
     fn update(&mut self, account_map: &jupiter_amm_interface::AccountMap) -> anyhow::Result<()> {
         match self.update_type {
             UpdateType::Custodies => {
-                let pool =
-                    Pool::try_deserialize(&mut try_get_account_data(account_map, &self.pool_key)?)?;
+                let pool_account = get_account(account_map, &self.pool_key)?;
+                validate_program_account::<Pool>(&self.pool_key, pool_account, &self.program_id)?;
 
-                self.pool = pool;
+                let pool = Pool::try_deserialize(&mut &pool_account.data[..])?;
 
-                self.lp_token_mint.1 = Some(Mint::unpack(&mut try_get_account_data(
-                    account_map,
+                let lp_token_mint = TokenMint::unpack(
                     &self.lp_token_mint.0,
-                )?)?);
+                    get_account(account_map, &self.lp_token_mint.0)?,
+                )?;
+
+                let mut custodies = HashMap::new();
 
-                for custody_key in &self.pool.custodies {
+                for custody_key in &pool.custodies {
                     if *custody_key != system_program::ID {
-                        let custody = Custody::try_deserialize(&mut try_get_account_data(
-                            account_map,
+                        let custody_account = get_account(account_map, custody_key)?;
+                        validate_program_account::<Custody>(
                             custody_key,
-                        )?)?;
-                        self.custodies.insert(*custody_key, custody);
+                            custody_account,
+                            &self.program_id,
+                        )?;
+
+                        let custody = Custody::try_deserialize(&mut &custody_account.data[..])?;
+
+                        if custody.pool != self.pool_key {
+                            return Err(AccountValidationError::CustodyPoolMismatch {
+                                custody: *custody_key,
+                                expected: self.pool_key,
+                                actual: custody.pool,
+                            }
+                            .into());
+                        }
+
+                        custodies.insert(*custody_key, custody);
                     }
                 }
 
+                let cortex_key = self.cortex_key();
+                let cortex_account = get_account(account_map, &cortex_key)?;
+                validate_program_account::<Cortex>(&cortex_key, cortex_account, &self.program_id)?;
+                let cortex = Cortex::try_deserialize(&mut &cortex_account.data[..])?;
+
+                let user_profiles = self.read_user_profiles(account_map)?;
+
+                self.fee_split = Some(FeeSplit::from_cortex(&cortex));
+                self.lp_token_mint.1 = Some(lp_token_mint.mint);
+                Arc::make_mut(&mut self.mints).insert(self.lp_token_mint.0, lp_token_mint);
+                self.pool = Arc::new(pool);
+                self.custodies = Arc::new(custodies);
+                self.user_profiles = Arc::new(user_profiles);
+
                 self.update_type = UpdateType::OraclesAndTokens;
             }
             UpdateType::OraclesAndTokens => {
-                let oracle_keys = self.custodies.values().map(|c| c.oracle.oracle_account);
+                let mut oracle_prices = HashMap::with_capacity(self.custodies.len());
+                let mut oracle_programs = HashMap::with_capacity(self.custodies.len());
+                let mut mints = (*self.mints).clone();
+
+                for custody in self.custodies.values() {
+                    let oracle_key = custody.oracle.oracle_account;
+                    let oracle_account = get_account(account_map, &oracle_key)?;
+
+                    let oracle_price = parse_oracle_account(
+                        oracle::decode_oracle_type(custody.oracle.oracle_type)?,
+                        &oracle_key,
+                        oracle_account,
+                        &self.program_id,
+                    )?;
 
-                for oracle_key in oracle_keys {
-                    let oracle_account = account_map
-                        .get(&oracle_key)
-                        .with_context(|| format!("Could not find address: {oracle_key}"))?
-                        .to_owned();
+                    oracle_prices.insert(oracle_key, oracle_price);
+                    oracle_programs.insert(oracle_key, oracle_account.owner);
 
-                    let oracle_price = OraclePrice::new_from_pyth_price_update_v2_account_info(
-                        &(oracle_key, oracle_account).into_account_info(),
-                    )?;
+                    let token_mint =
+                        TokenMint::unpack(&custody.mint, get_account(account_map, &custody.mint)?)?;
+
+                    mints.insert(custody.mint, token_mint);
+                }
 
-                    self.oracle_prices.insert(oracle_key, oracle_price);
+                self.oracle_prices = Arc::new(oracle_prices);
+                self.oracle_programs = Arc::new(oracle_programs);
+                self.mints = Arc::new(mints);
+                // Prices are already applied, failing here would leave them out
+                // of sync with the AUM. The error is kept for ALP quotes to report.
+                match self.live_aum_usd() {
+                    Ok(aum_usd) => {
+                        self.aum_usd = aum_usd;
+                        self.aum_error = None;
+                    }
+                    Err(err) => {
+                        self.aum_usd = self.stored_aum_usd();
+                        self.aum_error = Some(format!("{err:#}"));
+                    }
                 }
 
                 self.update_type = UpdateType::Custodies;
@@ -238,45 +534,26 @@ impl Amm for PoolAmm {
         &self,
         quote_params: &jupiter_amm_interface::QuoteParams,
     ) -> anyhow::Result<jupiter_amm_interface::Quote> {
-        let lp_token_mint_key = self.lp_token_mint.0;
-
-        let ComputeResult {
-            in_amount,
-            out_amount,
-            fee_amount,
-            fee_pct,
-        } = if lp_token_mint_key == quote_params.input_mint {
-            calculate_remove_liquidity(&self, quote_params)
-        } else if lp_token_mint_key == quote_params.output_mint {
-            calculate_add_liquidity(&self, quote_params)
-        } else {
-            calculate_swap(&self, quote_params)
-        }?;
+        let user_profile = self
+            .quoting_owner
+            .as_ref()
+            .and_then(|owner| self.user_profile(owner));
 
-        Ok(Quote {
-            min_in_amount: None,
-            min_out_amount: None,
-            in_amount,
-            out_amount,
-            fee_amount,
-            fee_mint: FEE_REDISTRIBUTION_MINT,
-            fee_pct,
-        })
+        self.quote_with_profile(quote_params, user_profile)
     }
 
     fn get_swap_and_account_metas(
         &self,
         swap_params: &jupiter_amm_interface::SwapParams,
     ) -> anyhow::Result<jupiter_amm_interface::SwapAndAccountMetas> {
-        let lp_token_mint_key = self.lp_token_mint.0;
+        self.check_route_allowed(&swap_params.source_mint, &swap_params.destination_mint)?;
 
-        let account_metas = if lp_token_mint_key == swap_params.source_mint {
-            get_remove_liquidity_metas(&self, swap_params)
-        } else if lp_token_mint_key == swap_params.destination_mint {
-            get_add_liquidity_metas(&self, swap_params)
-        } else {
-            get_swap_metas(&self, swap_params)
-        }?;
+        let account_metas =
+            match self.get_operation(&swap_params.source_mint, &swap_params.destination_mint) {
+                Operation::RemoveLiquidity => get_remove_liquidity_metas(self, swap_params),
+                Operation::AddLiquidity => get_add_liquidity_metas(self, swap_params),
+                Operation::Swap => get_swap_metas(self, swap_params),
+            }?;
 
         Ok(SwapAndAccountMetas {
             swap: jupiter_amm_interface::Swap::Saber, //TODO Switch to Adrena
@@ -284,6 +561,52 @@ impl Amm for PoolAmm {
         })
     }
 
+    fn get_accounts_len(&self) -> usize {
+        [
+            Operation::Swap,
+            Operation::AddLiquidity,
+            Operation::RemoveLiquidity,
+        ]
+        .into_iter()
+        .map(|operation| self.get_accounts_len_for(operation))
+        .max()
+        .unwrap_or_default()
+    }
+
+    /// Programs the Adrena instructions CPI into or read accounts of: the token
+    /// programs of the mints it moves and the programs owning its oracles,
+    /// sorted by name.
+    fn program_dependencies(&self) -> Vec<(Pubkey, String)> {
+        let mut programs = vec![(self.program_id, "adrena".to_string())];
+
+        for program_id in self
+            .mints
+            .values()
+            .map(|m| m.program_id)
+            .chain(self.oracle_programs.values().copied())
+        {
+            if programs.iter().any(|(p, _)| *p == program_id) {
+                continue;
+            }
+
+            // Owners are validated on update, anything else is not a dependency
+            let name = match program_id {
+                SPL_TOKEN_ID => "spl_token",
+                TOKEN_2022_ID => "spl_token_2022",
+                oracle::PYTH_RECEIVER_ID => "pyth_solana_receiver",
+                oracle::PYTH_PUSH_ORACLE_ID => "pyth_push_oracle",
+                p if oracle::PYTH_LEGACY_PROGRAM_IDS.contains(&p) => "pyth_oracle",
+                _ => continue,
+            };
+
+            programs.push((program_id, name.to_string()));
+        }
+
+        programs.sort_by(|a, b| (&a.1, a.0).cmp(&(&b.1, b.0)));
+
+        programs
+    }
+
     fn clone_amm(&self) -> Box<dyn Amm + Send + Sync> {
         Box::new(self.clone())
     }
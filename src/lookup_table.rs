@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use solana_sdk::{
+    address_lookup_table::instruction::{create_lookup_table, extend_lookup_table},
+    instruction::Instruction,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    PoolAmm, FEE_REDISTRIBUTION_MINT, LM_STAKING, PROTOCOL_FEE_RECIPIENT, REWARD_ORACLE_ACCOUNT,
+};
+
+/// Addresses per `ExtendLookupTable` instruction, keeps each one well under the
+/// transaction size limit.
+const MAX_ADDRESSES_PER_EXTEND: usize = 30;
+
+impl PoolAmm {
+    /// Every account used by the swap and liquidity instructions that doesn't
+    /// depend on the user, in a stable order.
+    pub fn get_lookup_table_addresses(&self) -> Vec<Pubkey> {
+        let lp_token_mint = self.lp_token_mint.0;
+        let lp_staking = self.pda(&[b"staking", lp_token_mint.as_ref()]);
+
+        let mut addresses = vec![
+            self.program_id,
+            self.cortex_key(),
+            self.pool_key,
+            lp_token_mint,
+            lp_staking,
+            LM_STAKING,
+            self.pda(&[b"lm_token_mint"]),
+            self.pda(&[b"staking_reward_token_vault", LM_STAKING.as_ref()]),
+            self.pda(&[b"staking_reward_token_vault", lp_staking.as_ref()]),
+            self.pda(&[
+                b"custody",
+                self.pool_key.as_ref(),
+                FEE_REDISTRIBUTION_MINT.as_ref(),
+            ]),
+            self.pda(&[
+                b"custody_token_account",
+                self.pool_key.as_ref(),
+                FEE_REDISTRIBUTION_MINT.as_ref(),
+            ]),
+            REWARD_ORACLE_ACCOUNT,
+            PROTOCOL_FEE_RECIPIENT,
+        ];
+
+        for custody_key in self.pool.custodies.iter() {
+            if let Some(custody) = self.custodies.get(custody_key) {
+                addresses.push(*custody_key);
+                addresses.push(custody.token_account);
+                addresses.push(custody.oracle.oracle_account);
+            }
+        }
+
+        // Sorted so that the table doesn't depend on the map's iteration order
+        let mut mints: Vec<&Pubkey> = self.mints.keys().collect();
+        mints.sort();
+
+        for mint in mints {
+            addresses.push(self.token_program(mint));
+        }
+
+        let mut seen = HashSet::new();
+        addresses.retain(|address| seen.insert(*address));
+
+        addresses
+    }
+
+    /// Instructions creating a lookup table for this pool and filling it with
+    /// `get_lookup_table_addresses`. Returns the table address with them.
+    pub fn get_lookup_table_instructions(
+        &self,
+        authority: Pubkey,
+        payer: Pubkey,
+        recent_slot: u64,
+    ) -> (Pubkey, Vec<Instruction>) {
+        let (create_instruction, lookup_table) = create_lookup_table(authority, payer, recent_slot);
+
+        let mut instructions = vec![create_instruction];
+
+        for addresses in self
+            .get_lookup_table_addresses()
+            .chunks(MAX_ADDRESSES_PER_EXTEND)
+        {
+            instructions.push(extend_lookup_table(
+                lookup_table,
+                authority,
+                Some(payer),
+                addresses.to_vec(),
+            ));
+        }
+
+        (lookup_table, instructions)
+    }
+}
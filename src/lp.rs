@@ -0,0 +1,194 @@
+use anyhow::Context;
+use jupiter_amm_interface::{QuoteParams, SwapMode};
+use num_traits::{FromPrimitive, Zero};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    quote::{calculate_add_liquidity, calculate_remove_liquidity},
+    utils::{price_to_decimal, usd_to_decimal},
+    PoolAmm, BPS_POWER,
+};
+
+#[derive(Clone, Debug)]
+pub struct CustodyNav {
+    pub custody: Pubkey,
+    pub mint: Pubkey,
+    pub owned_usd: u64,
+    /// Share of the pool's owned assets held by this custody, in BPS.
+    pub current_ratio: u16,
+    pub target_ratio: u16,
+    pub min_ratio: u16,
+    pub max_ratio: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct LpNav {
+    pub aum_usd: u128,
+    pub lp_supply: u64,
+    /// `None` while no ALP is in circulation.
+    pub lp_price_usd: Option<Decimal>,
+    pub custodies: Vec<CustodyNav>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LpExecutionPrice {
+    pub lp_amount: u64,
+    pub token_amount: u64,
+    pub fee_amount: u64,
+    /// USD paid (mint) or received (redeem) per ALP at this size.
+    pub price_usd: Decimal,
+    /// Difference with `lp_price_usd`, in percent. Positive when minting above
+    /// or redeeming below NAV.
+    pub premium_pct: Decimal,
+}
+
+impl PoolAmm {
+    /// AUM per ALP, `None` while no ALP is in circulation.
+    pub fn lp_price_usd(&self) -> anyhow::Result<Option<Decimal>> {
+        let lp_token_mint = self
+            .lp_token_mint
+            .1
+            .context("The mint of lp_token is not found.")?;
+
+        if lp_token_mint.supply.is_zero() {
+            return Ok(None);
+        }
+
+        let supply = Decimal::from_u64(lp_token_mint.supply)
+            .map(|supply| supply / Decimal::TEN.powu(lp_token_mint.decimals as u64))
+            .context("Can't convert lp supply")?;
+
+        usd_to_decimal(self.aum_usd)?
+            .checked_div(supply)
+            .context("Can't calculate lp price")
+            .map(Some)
+    }
+
+    pub fn lp_nav_breakdown(&self) -> anyhow::Result<LpNav> {
+        let lp_token_mint = self
+            .lp_token_mint
+            .1
+            .context("The mint of lp_token is not found.")?;
+
+        let mut custodies = Vec::with_capacity(self.custodies.len());
+
+        for (custody_key, custody) in self.custodies.iter() {
+            let (_, _, oracle_price) = self.get_custody_and_oracle(custody.mint)?;
+            let token_id = self.pool.get_token_id(custody_key)?;
+            let ratios = &self.pool.ratios[token_id];
+
+            custodies.push(CustodyNav {
+                custody: *custody_key,
+                mint: custody.mint,
+                owned_usd: oracle_price
+                    .get_asset_amount_usd(custody.assets.owned, custody.decimals)?,
+                current_ratio: 0,
+                target_ratio: ratios.target,
+                min_ratio: ratios.min,
+                max_ratio: ratios.max,
+            });
+        }
+
+        let total_owned_usd: u128 = custodies.iter().map(|c| c.owned_usd as u128).sum();
+
+        if !total_owned_usd.is_zero() {
+            for custody in &mut custodies {
+                custody.current_ratio =
+                    u16::try_from(custody.owned_usd as u128 * BPS_POWER as u128 / total_owned_usd)?;
+            }
+        }
+
+        custodies.sort_by_key(|c| self.pool.get_token_id(&c.custody).unwrap_or(usize::MAX));
+
+        Ok(LpNav {
+            aum_usd: self.aum_usd,
+            lp_supply: lp_token_mint.supply,
+            lp_price_usd: self.lp_price_usd()?,
+            custodies,
+        })
+    }
+
+    /// Price paid per ALP when minting with `amount` of `mint`, fees included.
+    pub fn lp_mint_price(&self, mint: Pubkey, amount: u64) -> anyhow::Result<LpExecutionPrice> {
+        let result = calculate_add_liquidity(
+            self,
+            &QuoteParams {
+                amount,
+                input_mint: mint,
+                output_mint: self.lp_token_mint.0,
+                swap_mode: SwapMode::ExactIn,
+            },
+        )?;
+
+        self.lp_execution_price(mint, result.out_amount, amount, result.fee_amount, false)
+    }
+
+    /// Price received per ALP when redeeming `lp_amount` into `mint`, fees included.
+    pub fn lp_redeem_price(
+        &self,
+        mint: Pubkey,
+        lp_amount: u64,
+    ) -> anyhow::Result<LpExecutionPrice> {
+        let result = calculate_remove_liquidity(
+            self,
+            &QuoteParams {
+                amount: lp_amount,
+                input_mint: self.lp_token_mint.0,
+                output_mint: mint,
+                swap_mode: SwapMode::ExactIn,
+            },
+        )?;
+
+        self.lp_execution_price(mint, lp_amount, result.out_amount, result.fee_amount, true)
+    }
+
+    fn lp_execution_price(
+        &self,
+        mint: Pubkey,
+        lp_amount: u64,
+        token_amount: u64,
+        fee_amount: u64,
+        is_redeem: bool,
+    ) -> anyhow::Result<LpExecutionPrice> {
+        let lp_token_mint = self
+            .lp_token_mint
+            .1
+            .context("The mint of lp_token is not found.")?;
+        let (_, custody, oracle_price) = self.get_custody_and_oracle(mint)?;
+
+        let token_amount_dec = Decimal::from_u64(token_amount)
+            .map(|amount| amount / Decimal::TEN.powu(custody.decimals as u64))
+            .context("Can't convert token amount")?;
+        let lp_amount_dec = Decimal::from_u64(lp_amount)
+            .map(|amount| amount / Decimal::TEN.powu(lp_token_mint.decimals as u64))
+            .context("Can't convert lp amount")?;
+
+        let price_usd = price_to_decimal(oracle_price)?
+            .checked_mul(token_amount_dec)
+            .and_then(|usd| usd.checked_div(lp_amount_dec))
+            .context("Can't calculate lp execution price")?;
+
+        let nav = self
+            .lp_price_usd()?
+            .context("No ALP in circulation to compare with")?;
+        let premium = if is_redeem {
+            nav - price_usd
+        } else {
+            price_usd - nav
+        };
+
+        let premium_pct = Decimal::ONE_HUNDRED
+            .checked_mul(premium)
+            .and_then(|per| per.checked_div(nav))
+            .context("Can't calculate premium percentage")?;
+
+        Ok(LpExecutionPrice {
+            lp_amount,
+            token_amount,
+            fee_amount,
+            price_usd,
+            premium_pct,
+        })
+    }
+}
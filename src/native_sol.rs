@@ -0,0 +1,90 @@
+use anyhow::anyhow;
+use jupiter_amm_interface::{Amm, SwapAndAccountMetas, SwapParams};
+use solana_sdk::{instruction::Instruction, pubkey as key, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::instruction::{close_account, sync_native};
+
+use crate::{PoolAmm, SPL_TOKEN_ID};
+
+pub const NATIVE_MINT: Pubkey = key!("So11111111111111111111111111111111111111112");
+
+/// A swap with the instructions needed to run it from and/or to native SOL. The
+/// user's wSOL associated token account is created and funded before the swap,
+/// and optionally closed back into the wallet after it.
+pub struct NativeSolSwap {
+    pub setup_instructions: Vec<Instruction>,
+    pub swap: SwapAndAccountMetas,
+    pub cleanup_instructions: Vec<Instruction>,
+}
+
+impl PoolAmm {
+    /// The wSOL account may already exist and hold wSOL of its own, which closing
+    /// it would unwrap as well. Leave `close_wsol_account` off to keep it.
+    pub fn get_native_sol_swap(
+        &self,
+        swap_params: &SwapParams,
+        close_wsol_account: bool,
+    ) -> anyhow::Result<NativeSolSwap> {
+        let owner = swap_params.token_transfer_authority;
+        let wsol_account =
+            get_associated_token_address_with_program_id(&owner, &NATIVE_MINT, &SPL_TOKEN_ID);
+
+        let mut setup_instructions = vec![];
+        let mut cleanup_instructions = vec![];
+
+        if swap_params.source_mint == NATIVE_MINT {
+            if swap_params.source_token_account != wsol_account {
+                return Err(anyhow!(
+                    "Source token account must be the wSOL associated token account {wsol_account}"
+                ));
+            }
+
+            setup_instructions.push(create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &NATIVE_MINT,
+                &SPL_TOKEN_ID,
+            ));
+            setup_instructions.push(system_instruction::transfer(
+                &owner,
+                &wsol_account,
+                swap_params.in_amount,
+            ));
+            setup_instructions.push(sync_native(&SPL_TOKEN_ID, &wsol_account)?);
+        }
+
+        if swap_params.destination_mint == NATIVE_MINT {
+            if swap_params.destination_token_account != wsol_account {
+                return Err(anyhow!(
+                    "Destination token account must be the wSOL associated token account {wsol_account}"
+                ));
+            }
+
+            setup_instructions.push(create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &NATIVE_MINT,
+                &SPL_TOKEN_ID,
+            ));
+        }
+
+        if close_wsol_account && !setup_instructions.is_empty() {
+            cleanup_instructions.push(close_account(
+                &SPL_TOKEN_ID,
+                &wsol_account,
+                &owner,
+                &owner,
+                &[],
+            )?);
+        }
+
+        Ok(NativeSolSwap {
+            setup_instructions,
+            swap: self.get_swap_and_account_metas(swap_params)?,
+            cleanup_instructions,
+        })
+    }
+}
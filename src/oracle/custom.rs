@@ -0,0 +1,33 @@
+use adrena::state::oracle::{CustomOracle, OraclePrice};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::ensure;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::AccountValidationError;
+
+use super::OracleParser;
+
+/// Adrena's own `CustomOracle` price account, for custodies using
+/// `OracleType::Custom`.
+pub struct CustomOracleParser;
+
+impl OracleParser for CustomOracleParser {
+    fn owners(&self, program_id: &Pubkey) -> Vec<Pubkey> {
+        vec![*program_id]
+    }
+
+    fn parse(&self, key: &Pubkey, account: &Account) -> anyhow::Result<OraclePrice> {
+        if !account.data.starts_with(&CustomOracle::DISCRIMINATOR) {
+            return Err(AccountValidationError::InvalidDiscriminator { account: *key }.into());
+        }
+
+        let oracle = CustomOracle::try_deserialize(&mut &account.data[..])?;
+
+        ensure!(
+            oracle.price > 0,
+            "Custom oracle price {key} is not positive"
+        );
+
+        Ok(OraclePrice::new(oracle.price, oracle.expo))
+    }
+}
@@ -0,0 +1,62 @@
+mod custom;
+mod pyth_legacy;
+mod pyth_price_update_v2;
+
+pub use custom::*;
+pub use pyth_legacy::*;
+pub use pyth_price_update_v2::*;
+
+use adrena::state::oracle::{OraclePrice, OracleType};
+use anchor_lang::AnchorDeserialize;
+use anyhow::anyhow;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::validation::validate_oracle_owner;
+
+/// Decodes `custody.oracle.oracle_type`, which zero-copy custodies store as the
+/// discriminant of the program's `OracleType`.
+///
+/// The batch price feed signed by ChaosLabs is not supported: `OracleType` in
+/// the program version this crate builds against has no variant for it, so a
+/// custody configured with it is rejected here instead of being mispriced.
+pub fn decode_oracle_type(oracle_type: u8) -> anyhow::Result<OracleType> {
+    OracleType::try_from_slice(&[oracle_type])
+        .map_err(|_| anyhow!("Unsupported oracle type: {oracle_type}"))
+}
+
+pub trait OracleParser {
+    /// Programs allowed to own accounts of this format.
+    fn owners(&self, program_id: &Pubkey) -> Vec<Pubkey>;
+
+    fn parse(&self, key: &Pubkey, account: &Account) -> anyhow::Result<OraclePrice>;
+}
+
+/// Picks the parser for a custody's oracle. Pyth custodies can point either at a
+/// `PriceUpdateV2` account or at a legacy Pyth price account, so those are told
+/// apart by the account owner.
+pub fn get_oracle_parser(
+    oracle_type: OracleType,
+    account: &Account,
+) -> anyhow::Result<&'static dyn OracleParser> {
+    match oracle_type {
+        OracleType::Custom => Ok(&CustomOracleParser),
+        OracleType::Pyth if PYTH_LEGACY_PROGRAM_IDS.contains(&account.owner) => {
+            Ok(&PythLegacyParser)
+        }
+        OracleType::Pyth => Ok(&PythPriceUpdateV2Parser),
+        OracleType::None => Err(anyhow!("Custody has no oracle configured")),
+    }
+}
+
+pub fn parse_oracle_account(
+    oracle_type: OracleType,
+    key: &Pubkey,
+    account: &Account,
+    program_id: &Pubkey,
+) -> anyhow::Result<OraclePrice> {
+    let parser = get_oracle_parser(oracle_type, account)?;
+
+    validate_oracle_owner(key, account, &parser.owners(program_id))?;
+
+    parser.parse(key, account)
+}
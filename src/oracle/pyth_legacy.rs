@@ -0,0 +1,69 @@
+use adrena::state::oracle::OraclePrice;
+use anyhow::{ensure, Context};
+use solana_sdk::{account::Account, pubkey as key, pubkey::Pubkey};
+
+use super::OracleParser;
+
+/// Mainnet and devnet programs of the legacy Pyth oracle, whose price accounts
+/// predate the receiver's `PriceUpdateV2`.
+pub const PYTH_LEGACY_PROGRAM_IDS: [Pubkey; 2] = [
+    key!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH"),
+    key!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s"),
+];
+
+const MAGIC: u32 = 0xa1b2c3d4;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+const MIN_LEN: usize = 240;
+
+pub struct PythLegacyParser;
+
+impl OracleParser for PythLegacyParser {
+    fn owners(&self, _program_id: &Pubkey) -> Vec<Pubkey> {
+        PYTH_LEGACY_PROGRAM_IDS.to_vec()
+    }
+
+    fn parse(&self, key: &Pubkey, account: &Account) -> anyhow::Result<OraclePrice> {
+        let data = &account.data;
+
+        ensure!(
+            data.len() >= MIN_LEN,
+            "Pyth price account {key} is too small"
+        );
+        ensure!(
+            read_u32(data, MAGIC_OFFSET)? == MAGIC,
+            "Account {key} is not a Pyth account"
+        );
+        ensure!(
+            read_u32(data, ACCOUNT_TYPE_OFFSET)? == ACCOUNT_TYPE_PRICE,
+            "Account {key} is not a Pyth price account"
+        );
+        ensure!(
+            read_u32(data, AGGREGATE_STATUS_OFFSET)? == STATUS_TRADING,
+            "Pyth price {key} is not trading"
+        );
+
+        let exponent = i32::from_le_bytes(read_bytes(data, EXPONENT_OFFSET)?);
+        let price = i64::from_le_bytes(read_bytes(data, AGGREGATE_PRICE_OFFSET)?);
+
+        ensure!(price > 0, "Pyth price {key} is not positive");
+
+        Ok(OraclePrice::new(price as u64, exponent))
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> anyhow::Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("Can't read Pyth price account")
+}
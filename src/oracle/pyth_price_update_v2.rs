@@ -0,0 +1,27 @@
+use adrena::state::oracle::OraclePrice;
+use solana_sdk::{account::Account, account_info::IntoAccountInfo, pubkey as key, pubkey::Pubkey};
+
+use crate::validation::validate_size;
+
+use super::OracleParser;
+
+pub const PYTH_RECEIVER_ID: Pubkey = key!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+/// Owns the sponsored price feeds, which are `PriceUpdateV2` accounts too.
+pub const PYTH_PUSH_ORACLE_ID: Pubkey = key!("pythWSnswVUd12oZpeFP8e9CVaEqJg25g1Vtc2biRsT");
+pub const PYTH_PRICE_UPDATE_V2_LEN: usize = 134;
+
+pub struct PythPriceUpdateV2Parser;
+
+impl OracleParser for PythPriceUpdateV2Parser {
+    fn owners(&self, _program_id: &Pubkey) -> Vec<Pubkey> {
+        vec![PYTH_RECEIVER_ID, PYTH_PUSH_ORACLE_ID]
+    }
+
+    fn parse(&self, key: &Pubkey, account: &Account) -> anyhow::Result<OraclePrice> {
+        validate_size(key, account, PYTH_PRICE_UPDATE_V2_LEN)?;
+
+        Ok(OraclePrice::new_from_pyth_price_update_v2_account_info(
+            &(*key, account.to_owned()).into_account_info(),
+        )?)
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::atomic::Ordering;
+
+use adrena::state::{custody::Custody, oracle::OraclePrice};
+use anyhow::{anyhow, Context};
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{utils::price_to_decimal, PoolAmm, BPS_POWER, RATE_DECIMALS, SECONDS_PER_HOUR};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Long,
+    Short,
+}
+
+/// A position as Adrena accounts for it. USD values are in dollars.
+#[derive(Clone, Debug)]
+pub struct PerpPosition {
+    pub side: Side,
+    /// Custody of the traded asset.
+    pub custody: Pubkey,
+    /// Custody the collateral is held in, and the borrow fee accrues on.
+    pub collateral_custody: Pubkey,
+    pub entry_price: Decimal,
+    pub size_usd: Decimal,
+    pub collateral_usd: Decimal,
+    /// Cumulative interest of the collateral custody when the borrow fee was
+    /// last settled, with `RATE_DECIMALS`.
+    pub cumulative_interest_snapshot: u128,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct OpenPositionParams {
+    pub side: Side,
+    /// Mint of the traded asset, e.g. WSOL for a SOL long or short.
+    pub mint: Pubkey,
+    /// Mint the collateral is posted in.
+    pub collateral_mint: Pubkey,
+    pub size_usd: Decimal,
+    /// Leverage in BPS, e.g. `50_000` for 5x.
+    pub leverage: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct OpenPositionQuote {
+    /// Oracle price moved against the trader by the custody's trade spread.
+    pub entry_price: Decimal,
+    pub open_fee_usd: Decimal,
+    /// Collateral backing the position once the open fee is paid.
+    pub collateral_usd: Decimal,
+    /// Amount of `collateral_mint` to post, open fee included.
+    pub required_collateral_amount: u64,
+    pub liquidation_price: Decimal,
+    pub position: PerpPosition,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClosePositionQuote {
+    /// Oracle price moved against the trader by the custody's trade spread.
+    pub exit_price: Decimal,
+    pub closed_size_usd: Decimal,
+    /// Realized on the closed size, negative for a loss.
+    pub pnl_usd: Decimal,
+    pub exit_fee_usd: Decimal,
+    pub borrow_fee_usd: Decimal,
+    /// Collateral released plus PnL minus fees, floored at zero.
+    pub settled_usd: Decimal,
+    /// Amount of the collateral mint paid out to the trader.
+    pub settled_amount: u64,
+    /// What is left open, `None` when the position is fully closed.
+    pub remaining: Option<PerpPosition>,
+}
+
+impl PoolAmm {
+    pub fn quote_open_position(
+        &self,
+        params: &OpenPositionParams,
+    ) -> anyhow::Result<OpenPositionQuote> {
+        let (custody_key, custody, oracle_price) = self.get_custody_and_oracle(params.mint)?;
+        let (collateral_custody_key, collateral_custody, collateral_price) =
+            self.get_custody_and_oracle(params.collateral_mint)?;
+
+        if params.size_usd <= Decimal::ZERO {
+            return Err(anyhow!("Position size must be positive"));
+        }
+
+        if (params.leverage as u64) < BPS_POWER
+            || params.leverage as u64 > custody.pricing.max_initial_leverage as u64
+        {
+            return Err(anyhow!(
+                "Leverage {} is outside of [{BPS_POWER}, {}] BPS",
+                params.leverage,
+                custody.pricing.max_initial_leverage
+            ));
+        }
+
+        let entry_price = trade_price(custody, oracle_price, params.side, true)?;
+        let open_fee_usd = bps(params.size_usd, custody.fees.open_position as u64)?;
+        let collateral_usd = params
+            .size_usd
+            .checked_mul(Decimal::from(BPS_POWER))
+            .and_then(|size| size.checked_div(Decimal::from(params.leverage as u64)))
+            .context("Can't calculate collateral")?;
+
+        // The pool locks what it would have to pay out: the traded asset for
+        // longs, the collateral for shorts.
+        let (locked_custody, locked_price) = match params.side {
+            Side::Long => (custody, oracle_price),
+            Side::Short => (collateral_custody, collateral_price),
+        };
+        let locked_amount = usd_to_token_amount(
+            params.size_usd,
+            price_to_decimal(locked_price)?,
+            locked_custody.decimals,
+            true,
+        )?;
+        let available_amount = locked_custody
+            .assets
+            .owned
+            .saturating_sub(locked_custody.assets.locked);
+        if locked_amount > available_amount {
+            return Err(anyhow!(
+                "Not enough liquidity: {locked_amount} needed, {available_amount} available"
+            ));
+        }
+
+        let position = PerpPosition {
+            side: params.side,
+            custody: custody_key,
+            collateral_custody: collateral_custody_key,
+            entry_price,
+            size_usd: params.size_usd,
+            collateral_usd,
+            cumulative_interest_snapshot: self.cumulative_interest(collateral_custody)?,
+        };
+
+        Ok(OpenPositionQuote {
+            entry_price,
+            open_fee_usd,
+            collateral_usd,
+            required_collateral_amount: usd_to_token_amount(
+                collateral_usd + open_fee_usd,
+                price_to_decimal(collateral_price)?,
+                collateral_custody.decimals,
+                true,
+            )?,
+            liquidation_price: self.liquidation_price(&position)?,
+            position,
+        })
+    }
+
+    /// Quotes closing `close_size_usd` of `position`, all of it when `None`.
+    pub fn quote_close_position(
+        &self,
+        position: &PerpPosition,
+        close_size_usd: Option<Decimal>,
+    ) -> anyhow::Result<ClosePositionQuote> {
+        let (custody, oracle_price) = self.get_custody_and_oracle_by_key(&position.custody)?;
+        let (collateral_custody, collateral_price) =
+            self.get_custody_and_oracle_by_key(&position.collateral_custody)?;
+
+        let closed_size_usd = close_size_usd.unwrap_or(position.size_usd);
+        if closed_size_usd <= Decimal::ZERO || closed_size_usd > position.size_usd {
+            return Err(anyhow!(
+                "Can't close {closed_size_usd} of a {} position",
+                position.size_usd
+            ));
+        }
+
+        let share = closed_size_usd / position.size_usd;
+        let exit_price = trade_price(custody, oracle_price, position.side, false)?;
+        let pnl_usd = pnl(
+            position.side,
+            position.entry_price,
+            exit_price,
+            closed_size_usd,
+        )?;
+        let exit_fee_usd = bps(closed_size_usd, custody.fees.close_position as u64)?;
+        let borrow_fee_usd = self.borrow_fee_usd(position)? * share;
+
+        let released_collateral_usd = position.collateral_usd * share;
+        let settled_usd =
+            (released_collateral_usd + pnl_usd - exit_fee_usd - borrow_fee_usd).max(Decimal::ZERO);
+
+        let remaining = (closed_size_usd < position.size_usd).then(|| PerpPosition {
+            size_usd: position.size_usd - closed_size_usd,
+            collateral_usd: position.collateral_usd - released_collateral_usd,
+            ..position.clone()
+        });
+
+        Ok(ClosePositionQuote {
+            exit_price,
+            closed_size_usd,
+            pnl_usd,
+            exit_fee_usd,
+            borrow_fee_usd,
+            settled_usd,
+            settled_amount: usd_to_token_amount(
+                settled_usd,
+                price_to_decimal(collateral_price)?,
+                collateral_custody.decimals,
+                false,
+            )?,
+            remaining,
+        })
+    }
+
+    /// Price at which the position's margin, after exit and borrow fees, falls
+    /// to `size / max_leverage`.
+    pub fn liquidation_price(&self, position: &PerpPosition) -> anyhow::Result<Decimal> {
+        let (custody, _) = self.get_custody_and_oracle_by_key(&position.custody)?;
+
+        let maintenance_margin_usd = self.maintenance_margin_usd(position)?;
+        let fees_usd = bps(position.size_usd, custody.fees.close_position as u64)?
+            + self.borrow_fee_usd(position)?;
+
+        let move_pct = (maintenance_margin_usd + fees_usd - position.collateral_usd)
+            .checked_div(position.size_usd)
+            .context("Can't calculate liquidation price")?;
+
+        let price = match position.side {
+            Side::Long => position.entry_price * (Decimal::ONE + move_pct),
+            Side::Short => position.entry_price * (Decimal::ONE - move_pct),
+        };
+
+        Ok(price.max(Decimal::ZERO))
+    }
+
+    /// Margin below which the position is liquidated, i.e. its size at the
+    /// custody's max leverage.
+    pub fn maintenance_margin_usd(&self, position: &PerpPosition) -> anyhow::Result<Decimal> {
+        let (custody, _) = self.get_custody_and_oracle_by_key(&position.custody)?;
+
+        position
+            .size_usd
+            .checked_mul(Decimal::from(BPS_POWER))
+            .and_then(|size| size.checked_div(Decimal::from(custody.pricing.max_leverage as u64)))
+            .context("Can't calculate maintenance margin")
+    }
+
+    /// Borrow fee accrued by the position since its last settlement.
+    pub fn borrow_fee_usd(&self, position: &PerpPosition) -> anyhow::Result<Decimal> {
+        let (collateral_custody, _) =
+            self.get_custody_and_oracle_by_key(&position.collateral_custody)?;
+
+        let interest = self
+            .cumulative_interest(collateral_custody)?
+            .saturating_sub(position.cumulative_interest_snapshot);
+
+        if interest.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+
+        Decimal::from_u128(interest)
+            .map(|interest| interest / Decimal::TEN.powu(RATE_DECIMALS as u64))
+            .and_then(|rate| position.size_usd.checked_mul(rate))
+            .context("Can't calculate borrow fee")
+    }
+
+    /// `borrow_rate_state.cumulative_interest` brought up to the current clock
+    /// at the custody's current hourly rate.
+    pub(crate) fn cumulative_interest(&self, custody: &Custody) -> anyhow::Result<u128> {
+        let state = &custody.borrow_rate_state;
+        let now = self.clock_ref.unix_timestamp.load(Ordering::Relaxed);
+        let elapsed = now.saturating_sub(state.last_update).max(0) as u128;
+
+        Ok(state.cumulative_interest.to_u128()
+            + state.current_rate as u128 * elapsed / SECONDS_PER_HOUR as u128)
+    }
+
+    fn get_custody_and_oracle_by_key(
+        &self,
+        custody_key: &Pubkey,
+    ) -> anyhow::Result<(&Custody, &OraclePrice)> {
+        let custody = self
+            .custodies
+            .get(custody_key)
+            .context(format!("Custody does not exist: {custody_key}"))?;
+        let (_, custody, oracle_price) = self.get_custody_and_oracle(custody.mint)?;
+
+        Ok((custody, oracle_price))
+    }
+}
+
+/// Oracle price with the trade spread applied against the trader: longs open
+/// above and close below the oracle, shorts the other way around.
+fn trade_price(
+    custody: &Custody,
+    oracle_price: &OraclePrice,
+    side: Side,
+    is_open: bool,
+) -> anyhow::Result<Decimal> {
+    let price = price_to_decimal(oracle_price)?;
+    let is_buy = matches!((side, is_open), (Side::Long, true) | (Side::Short, false));
+
+    Ok(if is_buy {
+        price + bps(price, custody.pricing.trade_spread_long as u64)?
+    } else {
+        price - bps(price, custody.pricing.trade_spread_short as u64)?
+    })
+}
+
+fn pnl(
+    side: Side,
+    entry_price: Decimal,
+    exit_price: Decimal,
+    size_usd: Decimal,
+) -> anyhow::Result<Decimal> {
+    let change = match side {
+        Side::Long => exit_price - entry_price,
+        Side::Short => entry_price - exit_price,
+    };
+
+    size_usd
+        .checked_mul(change)
+        .and_then(|pnl| pnl.checked_div(entry_price))
+        .context("Can't calculate pnl")
+}
+
+fn bps(value: Decimal, bps: u64) -> anyhow::Result<Decimal> {
+    value
+        .checked_mul(Decimal::from(bps))
+        .and_then(|value| value.checked_div(Decimal::from(BPS_POWER)))
+        .context("Can't apply bps")
+}
+
+fn usd_to_token_amount(
+    usd: Decimal,
+    price: Decimal,
+    decimals: u8,
+    round_up: bool,
+) -> anyhow::Result<u64> {
+    let amount = usd
+        .checked_div(price)
+        .and_then(|amount| amount.checked_mul(Decimal::TEN.powu(decimals as u64)))
+        .context("Can't convert usd to token amount")?;
+
+    if round_up {
+        amount.ceil()
+    } else {
+        amount.floor()
+    }
+    .to_u64()
+    .context("Can't convert usd to token amount")
+}
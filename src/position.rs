@@ -0,0 +1,140 @@
+use adrena::state::position::{Position, Side as PositionSide};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use anyhow::{anyhow, Context};
+use num_traits::FromPrimitive;
+use rust_decimal::Decimal;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{
+    utils::usd_to_decimal, validation::validate_program_account, AccountValidationError,
+    PerpPosition, PoolAmm, Side, PRICE_DECIMALS,
+};
+
+#[derive(Clone, Debug)]
+pub struct PositionHealth {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub side: Side,
+    pub size_usd: Decimal,
+    /// Price the position would close at now, trade spread included.
+    pub mark_price: Decimal,
+    pub unrealized_pnl_usd: Decimal,
+    pub borrow_fee_usd: Decimal,
+    pub exit_fee_usd: Decimal,
+    /// Collateral plus PnL minus exit and borrow fees. Negative once the
+    /// position is underwater.
+    pub margin_usd: Decimal,
+    /// `size / margin`, `None` when nothing is left of the margin.
+    pub leverage: Option<Decimal>,
+    pub liquidation_price: Decimal,
+    /// How far the mark price can move against the position before it gets
+    /// liquidated, in percent. Zero or negative when it already can be.
+    pub liquidation_distance_pct: Decimal,
+    pub is_liquidatable: bool,
+}
+
+impl PoolAmm {
+    /// Decodes an Adrena `Position` account of this pool.
+    pub fn decode_position(&self, key: &Pubkey, account: &Account) -> anyhow::Result<PerpPosition> {
+        Ok(self.decode_position_account(key, account)?.1)
+    }
+
+    /// Evaluates a `Position` account against the cached custodies and oracle
+    /// prices.
+    pub fn position_health(
+        &self,
+        key: &Pubkey,
+        account: &Account,
+    ) -> anyhow::Result<PositionHealth> {
+        let (owner, position) = self.decode_position_account(key, account)?;
+        let close = self.quote_close_position(&position, None)?;
+
+        let margin_usd =
+            position.collateral_usd + close.pnl_usd - close.exit_fee_usd - close.borrow_fee_usd;
+        let liquidation_price = self.liquidation_price(&position)?;
+
+        let distance = match position.side {
+            Side::Long => close.exit_price - liquidation_price,
+            Side::Short => liquidation_price - close.exit_price,
+        };
+        let liquidation_distance_pct = Decimal::ONE_HUNDRED
+            .checked_mul(distance)
+            .and_then(|per| per.checked_div(close.exit_price))
+            .context("Can't calculate liquidation distance")?;
+
+        Ok(PositionHealth {
+            position: *key,
+            owner,
+            side: position.side,
+            size_usd: position.size_usd,
+            mark_price: close.exit_price,
+            unrealized_pnl_usd: close.pnl_usd,
+            borrow_fee_usd: close.borrow_fee_usd,
+            exit_fee_usd: close.exit_fee_usd,
+            margin_usd,
+            leverage: (margin_usd > Decimal::ZERO).then(|| position.size_usd / margin_usd),
+            liquidation_price,
+            liquidation_distance_pct,
+            is_liquidatable: margin_usd <= self.maintenance_margin_usd(&position)?,
+        })
+    }
+
+    /// Evaluates many positions, e.g. the result of `getProgramAccounts`. A
+    /// position that can't be evaluated doesn't prevent the others from being.
+    pub fn position_health_batch(
+        &self,
+        positions: &[(Pubkey, Account)],
+    ) -> Vec<anyhow::Result<PositionHealth>> {
+        positions
+            .iter()
+            .map(|(key, account)| self.position_health(key, account))
+            .collect()
+    }
+
+    fn decode_position_account(
+        &self,
+        key: &Pubkey,
+        account: &Account,
+    ) -> anyhow::Result<(Pubkey, PerpPosition)> {
+        validate_program_account::<Position>(key, account, &self.program_id)?;
+        let position = Position::try_deserialize(&mut &account.data[..])?;
+
+        if position.pool != self.pool_key {
+            return Err(AccountValidationError::PositionPoolMismatch {
+                position: *key,
+                expected: self.pool_key,
+                actual: position.pool,
+            }
+            .into());
+        }
+
+        // Stored as the discriminant of the program's `Side`
+        let side = match PositionSide::try_from_slice(&[position.side]) {
+            Ok(PositionSide::Long) => Side::Long,
+            Ok(PositionSide::Short) => Side::Short,
+            _ => {
+                return Err(anyhow!(
+                    "Position {key} has an unknown side {}",
+                    position.side
+                ))
+            }
+        };
+
+        let entry_price = Decimal::from_u64(position.price)
+            .map(|price| price / Decimal::TEN.powu(PRICE_DECIMALS as u64))
+            .context("Can't convert entry price")?;
+
+        Ok((
+            position.owner,
+            PerpPosition {
+                side,
+                custody: position.custody,
+                collateral_custody: position.collateral_custody,
+                entry_price,
+                size_usd: usd_to_decimal(position.size_usd as u128)?,
+                collateral_usd: usd_to_decimal(position.collateral_usd as u128)?,
+                cumulative_interest_snapshot: position.cumulative_interest_snapshot.to_u128(),
+            },
+        ))
+    }
+}
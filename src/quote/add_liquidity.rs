@@ -7,16 +7,19 @@ use rust_decimal::Decimal;
 
 use crate::{
     PoolAmm, FEE_REDISTRIBUTION_MINT, LM_STAKING, PROTOCOL_FEE_RECIPIENT, REWARD_ORACLE_ACCOUNT,
-    SPL_TOKEN_ID,
 };
 
 use super::ComputeResult;
 
+/// Accounts in the `AddLiquidity` instruction built by `get_add_liquidity_metas`.
+pub const ADD_LIQUIDITY_ACCOUNTS_LEN: usize = 21;
+
 pub fn calculate_add_liquidity(
     amm: &PoolAmm,
     params: &QuoteParams,
 ) -> anyhow::Result<ComputeResult> {
     let in_amount = params.amount;
+    let deposit_amount = in_amount - amm.transfer_fee(&params.input_mint, in_amount)?;
 
     let lp_token_mint = amm
         .lp_token_mint
@@ -29,9 +32,9 @@ pub fn calculate_add_liquidity(
 
     let liquidity_fee =
         amm.pool
-            .get_add_liquidity_fee(token_id_in, in_amount, custody_in, token_price_in)?;
-    let pool_amount_usd = amm.pool.aum_usd.to_u128();
-    let token_amount = in_amount - liquidity_fee;
+            .get_add_liquidity_fee(token_id_in, deposit_amount, custody_in, token_price_in)?;
+    let pool_amount_usd = amm.aum_usd;
+    let token_amount = deposit_amount - liquidity_fee;
     let token_amount_usd =
         token_price_in.get_asset_amount_usd(token_amount, custody_in.decimals)?;
 
@@ -77,7 +80,7 @@ pub fn get_add_liquidity_metas(
 
     let lp_token_mint = amm.lp_token_mint.0;
     let lp_staking = amm.pda(&[b"staking", lp_token_mint.as_ref()]);
-    let cortex = amm.pda(&[b"cortex"]);
+    let cortex = amm.cortex_key();
 
     let lm_token_mint = amm.pda(&[b"lm_token_mint"]);
     let lm_staking_reward_token_vault =
@@ -109,13 +112,18 @@ pub fn get_add_liquidity_metas(
         staking_reward_token_custody_token_account,
         custody: *dispensing_custody,
         custody_oracle_account: dispensing_custody_state.oracle.oracle_account,
-        custody_token_account: params.source_token_account,
+        custody_token_account: amm
+            .get_custody_token_account(dispensing_custody, dispensing_custody_state)?,
         lm_staking_reward_token_vault,
         lp_staking_reward_token_vault,
         lm_token_mint,
         lp_token_mint,
         protocol_fee_recipient: PROTOCOL_FEE_RECIPIENT,
-        token_program: SPL_TOKEN_ID,
+        token_program: amm.instruction_token_program(&[
+            params.source_mint,
+            lp_token_mint,
+            FEE_REDISTRIBUTION_MINT,
+        ])?,
         adrena_program: amm.program_id,
     }
     .to_account_metas(None))
@@ -7,11 +7,13 @@ use rust_decimal::Decimal;
 
 use crate::{
     PoolAmm, FEE_REDISTRIBUTION_MINT, LM_STAKING, PROTOCOL_FEE_RECIPIENT, REWARD_ORACLE_ACCOUNT,
-    SPL_TOKEN_ID,
 };
 
 use super::ComputeResult;
 
+/// Accounts in the `RemoveLiquidity` instruction built by `get_remove_liquidity_metas`.
+pub const REMOVE_LIQUIDITY_ACCOUNTS_LEN: usize = 20;
+
 pub fn calculate_remove_liquidity(
     amm: &PoolAmm,
     params: &QuoteParams,
@@ -28,7 +30,9 @@ pub fn calculate_remove_liquidity(
     let token_id = amm.pool.get_token_id(&custody_pubkey)?;
 
     let remove_amount_usd = math::checked_as_u64(
-        (amm.pool.aum_usd.to_u128() * in_amount as u128) / lp_token_mint.supply as u128,
+        (amm.aum_usd * in_amount as u128)
+            .checked_div(lp_token_mint.supply as u128)
+            .context("No lp_token in circulation to redeem")?,
     )?;
 
     let remove_amount = token_price.get_token_amount(remove_amount_usd, custody.decimals)?;
@@ -36,6 +40,7 @@ pub fn calculate_remove_liquidity(
         amm.pool
             .get_remove_liquidity_fee(token_id, remove_amount, custody, &token_price)?;
     let out_amount = remove_amount - fee_amount;
+    let out_amount = out_amount - amm.transfer_fee(&params.output_mint, out_amount)?;
 
     let fee_amount_usd = token_price.get_asset_amount_usd(fee_amount, custody.decimals)?;
 
@@ -70,7 +75,7 @@ pub fn get_remove_liquidity_metas(
 
     let lp_token_mint = amm.lp_token_mint.0;
     let lp_staking = amm.pda(&[b"staking", lp_token_mint.as_ref()]);
-    let cortex = amm.pda(&[b"cortex"]);
+    let cortex = amm.cortex_key();
 
     let lm_staking_reward_token_vault =
         amm.pda(&[b"staking_reward_token_vault", LM_STAKING.as_ref()]);
@@ -100,12 +105,17 @@ pub fn get_remove_liquidity_metas(
         staking_reward_token_custody_token_account,
         custody: *receiving_custody,
         custody_oracle_account: receiving_custody_state.oracle.oracle_account,
-        custody_token_account: params.destination_token_account,
+        custody_token_account: amm
+            .get_custody_token_account(receiving_custody, receiving_custody_state)?,
         lm_staking_reward_token_vault,
         lp_staking_reward_token_vault,
         lp_token_mint,
         protocol_fee_recipient: PROTOCOL_FEE_RECIPIENT,
-        token_program: SPL_TOKEN_ID,
+        token_program: amm.instruction_token_program(&[
+            lp_token_mint,
+            params.destination_mint,
+            FEE_REDISTRIBUTION_MINT,
+        ])?,
         adrena_program: amm.program_id,
         receiving_account: params.destination_token_account,
     }
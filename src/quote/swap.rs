@@ -4,14 +4,22 @@ use anyhow::anyhow;
 use jupiter_amm_interface::{QuoteParams, SwapParams};
 
 use crate::{
-    CalculateFeesParams, PoolAmm, FEE_REDISTRIBUTION_MINT, LM_STAKING, PROTOCOL_FEE_RECIPIENT,
-    REWARD_ORACLE_ACCOUNT, SPL_TOKEN_ID,
+    CalculateFeesParams, PoolAmm, UserProfileState, BPS_POWER, FEE_REDISTRIBUTION_MINT, LM_STAKING,
+    PROTOCOL_FEE_RECIPIENT, REWARD_ORACLE_ACCOUNT,
 };
 
 use super::ComputeResult;
 
-pub fn calculate_swap(amm: &PoolAmm, params: &QuoteParams) -> anyhow::Result<ComputeResult> {
+/// Accounts in the `Swap` instruction built by `get_swap_metas`.
+pub const SWAP_ACCOUNTS_LEN: usize = 24;
+
+pub fn calculate_swap(
+    amm: &PoolAmm,
+    params: &QuoteParams,
+    user_profile: Option<&UserProfileState>,
+) -> anyhow::Result<ComputeResult> {
     let in_amount = params.amount;
+    let swap_amount = in_amount - amm.transfer_fee(&params.input_mint, in_amount)?;
 
     let (custody_in_pubkey, custody_in, token_price_in) =
         amm.get_custody_and_oracle(params.input_mint)?;
@@ -26,13 +34,13 @@ pub fn calculate_swap(amm: &PoolAmm, params: &QuoteParams) -> anyhow::Result<Com
         token_price_out,
         custody_in,
         custody_out,
-        in_amount,
+        swap_amount,
     )?;
 
     let fees = {
         let swap_fees_in = amm.pool.get_swap_in_fees(
             token_id_in,
-            in_amount,
+            swap_amount,
             custody_in,
             token_price_in,
             custody_out,
@@ -46,16 +54,24 @@ pub fn calculate_swap(amm: &PoolAmm, params: &QuoteParams) -> anyhow::Result<Com
             token_price_out,
         )?;
 
-        (swap_fees_in, swap_fees_out)
+        match user_profile {
+            Some(profile) => (
+                apply_discount(swap_fees_in, profile.fee_discount_bps),
+                apply_discount(swap_fees_out, profile.fee_discount_bps),
+            ),
+            None => (swap_fees_in, swap_fees_out),
+        }
     };
 
     let real_out_amount = out_amount - fees.1;
+    let real_out_amount =
+        real_out_amount - amm.transfer_fee(&params.output_mint, real_out_amount)?;
 
     let (fee_amount, fee_pct) = amm.calculate_swap_fees(CalculateFeesParams {
         fees,
         in_decimals: custody_in.decimals,
         in_oracle: token_price_in,
-        in_amount,
+        in_amount: swap_amount,
         out_amount,
         out_decimals: custody_out.decimals,
         out_oracle: token_price_out,
@@ -89,8 +105,11 @@ pub fn get_swap_metas(amm: &PoolAmm, params: &SwapParams) -> anyhow::Result<Vec<
 
     let lp_token_mint = amm.lp_token_mint.0;
     let lp_staking = amm.pda(&[b"staking", lp_token_mint.as_ref()]);
-    let cortex = amm.pda(&[b"cortex"]);
-    let user_profile = amm.pda(&[b"user_profile", params.token_transfer_authority.as_ref()]);
+    let cortex = amm.cortex_key();
+    // Only profiles known to exist, passing a missing one fails the instruction
+    let user_profile = amm
+        .user_profile(&params.token_transfer_authority)
+        .map(|_| amm.get_user_profile_key(&params.token_transfer_authority));
     let lm_staking_reward_token_vault =
         amm.pda(&[b"staking_reward_token_vault", LM_STAKING.as_ref()]);
     let lp_staking_reward_token_vault =
@@ -120,17 +139,30 @@ pub fn get_swap_metas(amm: &PoolAmm, params: &SwapParams) -> anyhow::Result<Vec<
         staking_reward_token_custody_token_account,
         receiving_custody: *receiving_custody,
         receiving_custody_oracle_account: receiving_custody_state.oracle.oracle_account,
-        receiving_custody_token_account: receiving_custody_state.token_account,
+        receiving_custody_token_account: amm
+            .get_custody_token_account(receiving_custody, receiving_custody_state)?,
         dispensing_custody: *dispensing_custody,
         dispensing_custody_oracle_account: dispensing_custody_state.oracle.oracle_account,
-        dispensing_custody_token_account: dispensing_custody_state.token_account,
+        dispensing_custody_token_account: amm
+            .get_custody_token_account(dispensing_custody, dispensing_custody_state)?,
         lm_staking_reward_token_vault,
         lp_staking_reward_token_vault,
         lp_token_mint,
         protocol_fee_recipient: PROTOCOL_FEE_RECIPIENT,
-        user_profile: Some(user_profile),
-        token_program: SPL_TOKEN_ID,
+        user_profile,
+        token_program: amm.instruction_token_program(&[
+            params.source_mint,
+            params.destination_mint,
+            FEE_REDISTRIBUTION_MINT,
+        ])?,
         adrena_program: amm.program_id,
     }
     .to_account_metas(None))
 }
+
+/// Fee left once the user's discount is taken off.
+fn apply_discount(fee: u64, discount_bps: u64) -> u64 {
+    let discount = fee as u128 * discount_bps.min(BPS_POWER) as u128 / BPS_POWER as u128;
+
+    fee - discount as u64
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{Operation, PoolAmm};
+
+/// Route restrictions, read from `KeyedAccount::params`, e.g.
+///
+/// ```json
+/// {
+///     "blockedPairs": [["3jdY...RqG", "So11...112"]],
+///     "allowLpRedeem": false
+/// }
+/// ```
+///
+/// Pairs are directional, `(input_mint, output_mint)`. When `allowedPairs` is
+/// set, routes not listed there, ALP mint and redeem included, are refused.
+/// Params that are not an object, e.g. `null`, leave every route enabled.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RouteConfig {
+    #[serde_as(as = "Option<Vec<(DisplayFromStr, DisplayFromStr)>>")]
+    pub allowed_pairs: Option<Vec<(Pubkey, Pubkey)>>,
+    #[serde_as(as = "Vec<(DisplayFromStr, DisplayFromStr)>")]
+    pub blocked_pairs: Vec<(Pubkey, Pubkey)>,
+    pub allow_lp_mint: bool,
+    pub allow_lp_redeem: bool,
+}
+
+impl Default for RouteConfig {
+    fn default() -> Self {
+        RouteConfig {
+            allowed_pairs: None,
+            blocked_pairs: vec![],
+            allow_lp_mint: true,
+            allow_lp_redeem: true,
+        }
+    }
+}
+
+impl RouteConfig {
+    pub fn from_params(params: Option<&serde_json::Value>) -> anyhow::Result<Self> {
+        match params {
+            Some(params) if params.is_object() => Ok(serde_json::from_value(params.clone())?),
+            _ => Ok(RouteConfig::default()),
+        }
+    }
+}
+
+impl PoolAmm {
+    pub fn route_config(&self) -> &RouteConfig {
+        &self.route_config
+    }
+
+    pub fn is_route_allowed(&self, input_mint: &Pubkey, output_mint: &Pubkey) -> bool {
+        let config = &self.route_config;
+        let pair = (*input_mint, *output_mint);
+
+        match self.get_operation(input_mint, output_mint) {
+            Operation::AddLiquidity if !config.allow_lp_mint => return false,
+            Operation::RemoveLiquidity if !config.allow_lp_redeem => return false,
+            _ => {}
+        }
+
+        if config.blocked_pairs.contains(&pair) {
+            return false;
+        }
+
+        match &config.allowed_pairs {
+            Some(allowed_pairs) => allowed_pairs.contains(&pair),
+            None => true,
+        }
+    }
+
+    pub(crate) fn check_route_allowed(
+        &self,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+    ) -> anyhow::Result<()> {
+        if !self.is_route_allowed(input_mint, output_mint) {
+            return Err(anyhow::anyhow!(
+                "Route {input_mint} -> {output_mint} is disabled"
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+use std::{collections::HashMap, sync::Arc};
+
+use adrena::state::oracle::OraclePrice;
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::{Amm, QuoteParams};
+use num_traits::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::PoolAmm;
+
+#[derive(Clone, Copy, Debug)]
+pub enum PriceOverride {
+    /// Replaces the oracle price, expressed as `price * 10^exponent`.
+    Absolute { price: u64, exponent: i32 },
+    /// Moves the current oracle price by a percentage, e.g. `-10` for a 10% drop.
+    /// Must be above `-100`.
+    ShockPct(Decimal),
+}
+
+#[derive(Clone, Debug)]
+pub struct QuoteComparison {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount: u64,
+    pub base_out_amount: u64,
+    pub scenario_out_amount: u64,
+    pub out_amount_change_pct: Decimal,
+}
+
+impl PoolAmm {
+    /// Returns a copy of this quoter with the given per-mint oracle prices
+    /// overridden. The pool AUM is recomputed from the new prices so that ALP
+    /// mint/redeem quotes stay consistent with swaps.
+    pub fn with_price_overrides(
+        &self,
+        overrides: &HashMap<Pubkey, PriceOverride>,
+    ) -> anyhow::Result<PoolAmm> {
+        let mut scenario = self.clone();
+
+        for (mint, price_override) in overrides {
+            let (_, custody, current_price) = self.get_custody_and_oracle(*mint)?;
+
+            let new_price = match *price_override {
+                PriceOverride::Absolute { price, exponent } => OraclePrice::new(price, exponent),
+                PriceOverride::ShockPct(pct) => {
+                    if pct <= -Decimal::ONE_HUNDRED {
+                        return Err(anyhow!(
+                            "Can't shock price of {mint} by {pct}%, prices must stay positive"
+                        ));
+                    }
+
+                    let factor = (Decimal::ONE_HUNDRED + pct) / Decimal::ONE_HUNDRED;
+                    let price = Decimal::from_u64(current_price.price)
+                        .and_then(|price| price.checked_mul(factor))
+                        .and_then(|price| price.floor().to_u64())
+                        .context(format!("Can't shock price of {mint} by {pct}%"))?;
+
+                    OraclePrice::new(price, current_price.exponent)
+                }
+            };
+
+            Arc::make_mut(&mut scenario.oracle_prices)
+                .insert(custody.oracle.oracle_account, new_price);
+        }
+
+        scenario.aum_usd = scenario.live_aum_usd()?;
+
+        Ok(scenario)
+    }
+
+    /// Quotes the same requests against `self` and `scenario` side by side.
+    pub fn compare_quotes(
+        &self,
+        scenario: &PoolAmm,
+        quote_params: &[QuoteParams],
+    ) -> anyhow::Result<Vec<QuoteComparison>> {
+        quote_params
+            .iter()
+            .map(|params| {
+                let base = self.quote(params)?;
+                let shocked = scenario.quote(params)?;
+
+                let base_out =
+                    Decimal::from_u64(base.out_amount).context("Can't convert out_amount")?;
+                let shocked_out =
+                    Decimal::from_u64(shocked.out_amount).context("Can't convert out_amount")?;
+
+                let out_amount_change_pct = if base_out.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    Decimal::ONE_HUNDRED
+                        .checked_mul(shocked_out - base_out)
+                        .and_then(|per| per.checked_div(base_out))
+                        .context("Can't calculate out_amount change")?
+                };
+
+                Ok(QuoteComparison {
+                    input_mint: params.input_mint,
+                    output_mint: params.output_mint,
+                    amount: params.amount,
+                    base_out_amount: base.out_amount,
+                    scenario_out_amount: shocked.out_amount,
+                    out_amount_change_pct,
+                })
+            })
+            .collect()
+    }
+}
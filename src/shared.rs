@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use anchor_lang::system_program;
+use arc_swap::ArcSwap;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{AccountSource, PoolAmm};
+
+/// A `PoolAmm` shared between quoting threads and a single updater. Readers
+/// never block: they get the last fully updated state, while the updater
+/// applies every update phase to its own O(1) clone before publishing it.
+#[derive(Clone)]
+pub struct SharedPoolAmm {
+    amm: Arc<ArcSwap<PoolAmm>>,
+}
+
+impl SharedPoolAmm {
+    pub fn new(amm: PoolAmm) -> Self {
+        SharedPoolAmm {
+            amm: Arc::new(ArcSwap::from_pointee(amm)),
+        }
+    }
+
+    /// Latest published state, unaffected by updates published afterwards.
+    pub fn current(&self) -> Arc<PoolAmm> {
+        self.amm.load_full()
+    }
+
+    pub fn store(&self, amm: PoolAmm) {
+        self.amm.store(Arc::new(amm));
+    }
+
+    /// Runs every update phase on a copy and publishes it once complete, so
+    /// readers never quote custodies against the previous oracle prices.
+    pub fn refresh(&self, source: &dyn AccountSource) -> anyhow::Result<()> {
+        let mut amm = PoolAmm::clone(&self.current());
+        amm.refresh(source)?;
+        self.store(amm);
+
+        Ok(())
+    }
+}
+
+impl PoolAmm {
+    /// Every account the quote depends on: pool, LP mint, custodies, their
+    /// oracles and mints, the Cortex holding the fee split and tracked user
+    /// profiles. Sorted, so that keys of the same accounts compare equal
+    /// whatever the order custodies are held in.
+    pub fn subscription_keys(&self) -> Vec<Pubkey> {
+        let mut keys: Vec<Pubkey> = [self.pool_key, self.lp_token_mint.0, self.cortex_key()]
+            .into_iter()
+            .chain(
+                self.pool
+                    .custodies
+                    .into_iter()
+                    .filter(|key| *key != system_program::ID),
+            )
+            .chain(
+                self.custodies
+                    .values()
+                    .flat_map(|c| [c.oracle.oracle_account, c.mint]),
+            )
+            .chain(
+                self.tracked_profile_owners()
+                    .iter()
+                    .map(|owner| self.get_user_profile_key(owner)),
+            )
+            .collect();
+
+        keys.sort();
+        keys.dedup();
+
+        keys
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use adrena::state::{custody::Custody, oracle::OraclePrice, pool::Pool};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator};
+use anyhow::{anyhow, Context};
+use jupiter_amm_interface::ClockRef;
+use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as, DisplayFromStr, IfIsHumanReadable};
+use solana_sdk::{clock::Clock, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::transfer_fee::TransferFeeConfig, solana_program::program_pack::Pack, state::Mint,
+};
+use std::sync::{atomic::Ordering, Arc};
+
+use crate::{FeeSplit, PoolAmm, RouteConfig, TokenMint, UpdateType, UserProfileState};
+
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+type Key = IfIsHumanReadable<DisplayFromStr>;
+type Bytes = IfIsHumanReadable<Base64>;
+
+/// Everything a `PoolAmm` holds, in a form that can be written to disk and
+/// restored in another process. Program accounts are kept as their raw account
+/// data so restored instances quote exactly like the original.
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PoolAmmSnapshot {
+    pub version: u16,
+    #[serde_as(as = "Key")]
+    pub program_id: Pubkey,
+    #[serde_as(as = "Key")]
+    pub pool_key: Pubkey,
+    #[serde_as(as = "Bytes")]
+    pub pool: Vec<u8>,
+    pub aum_usd: u128,
+    pub aum_error: Option<String>,
+    #[serde_as(as = "Vec<(Key, Bytes)>")]
+    pub custodies: Vec<(Pubkey, Vec<u8>)>,
+    /// Borsh encoded `OraclePrice`s.
+    #[serde_as(as = "Vec<(Key, Bytes)>")]
+    pub oracle_prices: Vec<(Pubkey, Vec<u8>)>,
+    #[serde_as(as = "Vec<(Key, Key)>")]
+    pub oracle_programs: Vec<(Pubkey, Pubkey)>,
+    #[serde_as(as = "Key")]
+    pub lp_token_mint: Pubkey,
+    pub mints: Vec<TokenMintSnapshot>,
+    pub update_type: UpdateType,
+    pub slot: u64,
+    pub epoch: u64,
+    pub unix_timestamp: i64,
+    pub route_config: RouteConfig,
+    pub user_profiles: Vec<UserProfileSnapshot>,
+    #[serde_as(as = "Option<Key>")]
+    pub quoting_owner: Option<Pubkey>,
+    pub fee_split: Option<FeeSplit>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TokenMintSnapshot {
+    #[serde_as(as = "Key")]
+    pub mint_key: Pubkey,
+    #[serde_as(as = "Key")]
+    pub program_id: Pubkey,
+    #[serde_as(as = "Bytes")]
+    pub mint: Vec<u8>,
+    #[serde_as(as = "Option<Bytes>")]
+    pub transfer_fee_config: Option<Vec<u8>>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UserProfileSnapshot {
+    #[serde_as(as = "Key")]
+    pub owner: Pubkey,
+    /// `false` for owners known not to have a profile.
+    pub exists: bool,
+    #[serde_as(as = "Option<Key>")]
+    pub referrer: Option<Pubkey>,
+    pub fee_discount_bps: u64,
+}
+
+impl PoolAmmSnapshot {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let snapshot: Self = serde_json::from_str(json)?;
+        snapshot.check_version()?;
+
+        Ok(snapshot)
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let snapshot: Self = bincode::deserialize(bytes)?;
+        snapshot.check_version()?;
+
+        Ok(snapshot)
+    }
+
+    fn check_version(&self) -> anyhow::Result<()> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(anyhow!(
+                "Unsupported snapshot version {}, expected {SNAPSHOT_VERSION}",
+                self.version
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl PoolAmm {
+    pub fn snapshot(&self) -> anyhow::Result<PoolAmmSnapshot> {
+        let mut custodies: Vec<(Pubkey, Vec<u8>)> = self
+            .custodies
+            .iter()
+            .map(|(key, custody)| (*key, account_data(custody)))
+            .collect();
+        custodies.sort_by_key(|(key, _)| *key);
+
+        let mut oracle_prices: Vec<(Pubkey, Vec<u8>)> = self
+            .oracle_prices
+            .iter()
+            .map(|(key, price)| Ok((*key, price.try_to_vec()?)))
+            .collect::<anyhow::Result<_>>()?;
+        oracle_prices.sort_by_key(|(key, _)| *key);
+
+        let mut oracle_programs: Vec<(Pubkey, Pubkey)> = self
+            .oracle_programs
+            .iter()
+            .map(|(key, program)| (*key, *program))
+            .collect();
+        oracle_programs.sort_by_key(|(key, _)| *key);
+
+        let mut mints: Vec<TokenMintSnapshot> = self
+            .mints
+            .iter()
+            .map(|(key, token_mint)| {
+                let mut mint = vec![0u8; Mint::LEN];
+                token_mint.mint.pack_into_slice(&mut mint);
+
+                TokenMintSnapshot {
+                    mint_key: *key,
+                    program_id: token_mint.program_id,
+                    mint,
+                    transfer_fee_config: token_mint
+                        .transfer_fee_config
+                        .map(|config| bytemuck::bytes_of(&config).to_vec()),
+                }
+            })
+            .collect();
+        mints.sort_by_key(|m| m.mint_key);
+
+        let mut user_profiles: Vec<UserProfileSnapshot> = self
+            .user_profiles
+            .iter()
+            .map(|(owner, profile)| UserProfileSnapshot {
+                owner: *owner,
+                exists: profile.is_some(),
+                referrer: profile.as_ref().and_then(|p| p.referrer),
+                fee_discount_bps: profile
+                    .as_ref()
+                    .map(|p| p.fee_discount_bps)
+                    .unwrap_or_default(),
+            })
+            .collect();
+        user_profiles.sort_by_key(|p| p.owner);
+
+        Ok(PoolAmmSnapshot {
+            version: SNAPSHOT_VERSION,
+            program_id: self.program_id,
+            pool_key: self.pool_key,
+            pool: account_data(&*self.pool),
+            aum_usd: self.aum_usd,
+            aum_error: self.aum_error.clone(),
+            custodies,
+            oracle_prices,
+            oracle_programs,
+            lp_token_mint: self.lp_token_mint.0,
+            mints,
+            update_type: self.update_type.clone(),
+            slot: self.clock_ref.slot.load(Ordering::Relaxed),
+            epoch: self.clock_ref.epoch.load(Ordering::Relaxed),
+            unix_timestamp: self.clock_ref.unix_timestamp.load(Ordering::Relaxed),
+            route_config: (*self.route_config).clone(),
+            user_profiles,
+            quoting_owner: self.quoting_owner,
+            fee_split: self.fee_split,
+        })
+    }
+
+    pub fn from_snapshot(snapshot: &PoolAmmSnapshot) -> anyhow::Result<Self> {
+        snapshot.check_version()?;
+
+        let custodies = snapshot
+            .custodies
+            .iter()
+            .map(|(key, data)| Ok((*key, Custody::try_deserialize(&mut &data[..])?)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+        let mints = snapshot
+            .mints
+            .iter()
+            .map(|m| {
+                Ok((
+                    m.mint_key,
+                    TokenMint {
+                        program_id: m.program_id,
+                        mint: Mint::unpack_from_slice(&m.mint)?,
+                        transfer_fee_config: m
+                            .transfer_fee_config
+                            .as_ref()
+                            .map(|config| {
+                                bytemuck::try_pod_read_unaligned::<TransferFeeConfig>(config)
+                            })
+                            .transpose()
+                            .map_err(|e| anyhow!("Can't read transfer fee config: {e}"))?,
+                    },
+                ))
+            })
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+        let lp_token_mint = mints
+            .get(&snapshot.lp_token_mint)
+            .map(|token_mint| token_mint.mint);
+
+        Ok(PoolAmm {
+            pool_key: snapshot.pool_key,
+            pool: Arc::new(
+                Pool::try_deserialize(&mut &snapshot.pool[..]).context("Can't restore pool")?,
+            ),
+            aum_usd: snapshot.aum_usd,
+            aum_error: snapshot.aum_error.clone(),
+            custodies: Arc::new(custodies),
+            oracle_prices: Arc::new(
+                snapshot
+                    .oracle_prices
+                    .iter()
+                    .map(|(key, price)| Ok((*key, OraclePrice::try_from_slice(price)?)))
+                    .collect::<anyhow::Result<_>>()
+                    .context("Can't restore oracle prices")?,
+            ),
+            lp_token_mint: (snapshot.lp_token_mint, lp_token_mint),
+            mints: Arc::new(mints),
+            program_id: snapshot.program_id,
+            update_type: snapshot.update_type.clone(),
+            clock_ref: ClockRef::from(Clock {
+                slot: snapshot.slot,
+                epoch: snapshot.epoch,
+                unix_timestamp: snapshot.unix_timestamp,
+                ..Clock::default()
+            }),
+            oracle_programs: Arc::new(snapshot.oracle_programs.iter().copied().collect()),
+            route_config: Arc::new(snapshot.route_config.clone()),
+            user_profiles: Arc::new(
+                snapshot
+                    .user_profiles
+                    .iter()
+                    .map(|p| {
+                        let profile = p.exists.then_some(UserProfileState {
+                            referrer: p.referrer,
+                            fee_discount_bps: p.fee_discount_bps,
+                        });
+
+                        (p.owner, profile)
+                    })
+                    .collect(),
+            ),
+            quoting_owner: snapshot.quoting_owner,
+            fee_split: snapshot.fee_split,
+        })
+    }
+}
+
+fn account_data<T: bytemuck::Pod + Discriminator>(account: &T) -> Vec<u8> {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(account));
+    data
+}
@@ -0,0 +1,48 @@
+use anyhow::Context;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+
+use crate::{AccountValidationError, SPL_TOKEN_ID, TOKEN_2022_ID};
+
+/// A mint together with the program owning it and the Token-2022 extensions
+/// that affect quoting.
+#[derive(Clone, Debug)]
+pub struct TokenMint {
+    pub program_id: Pubkey,
+    pub mint: Mint,
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+}
+
+impl TokenMint {
+    pub fn unpack(key: &Pubkey, account: &Account) -> anyhow::Result<Self> {
+        if account.owner != SPL_TOKEN_ID && account.owner != TOKEN_2022_ID {
+            return Err(AccountValidationError::InvalidTokenProgram {
+                account: *key,
+                actual: account.owner,
+            }
+            .into());
+        }
+
+        let state = StateWithExtensions::<Mint>::unpack(&account.data)
+            .context(format!("Can't unpack mint {key}"))?;
+
+        Ok(TokenMint {
+            program_id: account.owner,
+            mint: state.base,
+            transfer_fee_config: state.get_extension::<TransferFeeConfig>().ok().copied(),
+        })
+    }
+
+    /// Fee withheld by the token program when transferring `amount` during `epoch`.
+    pub fn transfer_fee(&self, epoch: u64, amount: u64) -> anyhow::Result<u64> {
+        match &self.transfer_fee_config {
+            Some(config) => config
+                .calculate_epoch_fee(epoch, amount)
+                .context("Can't calculate transfer fee"),
+            None => Ok(0),
+        }
+    }
+}
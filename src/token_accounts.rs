@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::PoolAmm;
+
+impl PoolAmm {
+    /// Associated token account of `owner` for `mint`, derived with the token
+    /// program owning the mint.
+    pub fn get_user_token_account(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program(mint))
+    }
+
+    /// Associated token accounts of `owner` for every custody mint and the LP
+    /// mint, keyed by mint.
+    pub fn get_user_token_accounts(&self, owner: &Pubkey) -> HashMap<Pubkey, Pubkey> {
+        self.custodies
+            .values()
+            .map(|c| c.mint)
+            .chain(std::iter::once(self.lp_token_mint.0))
+            .map(|mint| (mint, self.get_user_token_account(owner, &mint)))
+            .collect()
+    }
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, sync::Arc};
+
+use adrena::state::user_profile::UserProfile;
+use anchor_lang::AccountDeserialize;
+use anyhow::anyhow;
+use jupiter_amm_interface::{AccountMap, Quote, QuoteParams};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{validation::validate_program_account, AccountSource, PoolAmm};
+
+/// What quotes need from an Adrena `UserProfile`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserProfileState {
+    pub referrer: Option<Pubkey>,
+    /// Discount on swap fees, in BPS.
+    pub fee_discount_bps: u64,
+}
+
+impl PoolAmm {
+    pub fn get_user_profile_key(&self, owner: &Pubkey) -> Pubkey {
+        self.pda(&[b"user_profile", owner.as_ref()])
+    }
+
+    /// Records the profile of `owner`, `account` being `None` when it doesn't
+    /// exist, and keeps it up to date on later updates. Swaps are only built
+    /// with the profiles loaded this way that exist, as a missing profile
+    /// account fails the instruction.
+    pub fn load_user_profile(
+        &mut self,
+        owner: Pubkey,
+        account: Option<&Account>,
+    ) -> anyhow::Result<()> {
+        let profile = account
+            .map(|account| self.decode_user_profile(&owner, account))
+            .transpose()?;
+
+        Arc::make_mut(&mut self.user_profiles).insert(owner, profile);
+
+        Ok(())
+    }
+
+    pub fn fetch_user_profile(
+        &mut self,
+        source: &dyn AccountSource,
+        owner: Pubkey,
+    ) -> anyhow::Result<()> {
+        let key = self.get_user_profile_key(&owner);
+        let account = source.get_accounts(&[key])?.remove(&key);
+
+        self.load_user_profile(owner, account.as_ref())
+    }
+
+    /// Makes `quote` apply the fee discount of `owner`'s profile, which is
+    /// loaded on the next update.
+    ///
+    /// This changes every later `quote` of this `PoolAmm`, its clones and
+    /// snapshots, whoever they are for. On a pool shared between users, quote
+    /// each of them with `quote_for_user` instead.
+    pub fn set_quoting_owner(&mut self, owner: Option<Pubkey>) {
+        self.quoting_owner = owner;
+    }
+
+    /// Profile of `owner`, if it was loaded and exists.
+    pub fn user_profile(&self, owner: &Pubkey) -> Option<&UserProfileState> {
+        self.user_profiles.get(owner)?.as_ref()
+    }
+
+    /// Like `quote`, with the fee discount of `owner`'s profile applied to swaps.
+    pub fn quote_for_user(
+        &self,
+        quote_params: &QuoteParams,
+        owner: &Pubkey,
+    ) -> anyhow::Result<Quote> {
+        self.quote_with_profile(quote_params, self.user_profile(owner))
+    }
+
+    /// Owners whose profile is refreshed on update.
+    pub(crate) fn tracked_profile_owners(&self) -> Vec<Pubkey> {
+        let mut owners: Vec<Pubkey> = self
+            .user_profiles
+            .keys()
+            .copied()
+            .chain(self.quoting_owner)
+            .collect();
+
+        owners.sort();
+        owners.dedup();
+
+        owners
+    }
+
+    /// Profiles of tracked owners, missing from `account_map` when they don't
+    /// exist.
+    pub(crate) fn read_user_profiles(
+        &self,
+        account_map: &AccountMap,
+    ) -> anyhow::Result<HashMap<Pubkey, Option<UserProfileState>>> {
+        self.tracked_profile_owners()
+            .into_iter()
+            .map(|owner| {
+                let profile = account_map
+                    .get(&self.get_user_profile_key(&owner))
+                    .map(|account| self.decode_user_profile(&owner, account))
+                    .transpose()?;
+
+                Ok((owner, profile))
+            })
+            .collect()
+    }
+
+    fn decode_user_profile(
+        &self,
+        owner: &Pubkey,
+        account: &Account,
+    ) -> anyhow::Result<UserProfileState> {
+        let key = self.get_user_profile_key(owner);
+        validate_program_account::<UserProfile>(&key, account, &self.program_id)?;
+
+        let profile = UserProfile::try_deserialize(&mut &account.data[..])?;
+
+        if profile.owner != *owner {
+            return Err(anyhow!(
+                "User profile {key} belongs to {}, expected {owner}",
+                profile.owner
+            ));
+        }
+
+        Ok(UserProfileState {
+            referrer: (profile.referrer_profile != Pubkey::default())
+                .then_some(profile.referrer_profile),
+            fee_discount_bps: profile.fee_discount_bps as u64,
+        })
+    }
+}
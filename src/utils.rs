@@ -0,0 +1,31 @@
+use adrena::state::oracle::OraclePrice;
+use anyhow::Context;
+use num_traits::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::USD_DECIMALS;
+
+pub fn price_to_decimal(price: &OraclePrice) -> anyhow::Result<Decimal> {
+    let value = Decimal::from_u64(price.price).context("Can't convert oracle price")?;
+    let scale = Decimal::TEN.powi(price.exponent.abs() as i64);
+
+    if price.exponent < 0 {
+        value.checked_div(scale)
+    } else {
+        value.checked_mul(scale)
+    }
+    .context("Can't scale oracle price")
+}
+
+pub fn usd_to_decimal(amount_usd: u128) -> anyhow::Result<Decimal> {
+    Decimal::from_u128(amount_usd)
+        .map(|amount| amount / Decimal::TEN.powu(USD_DECIMALS as u64))
+        .context("Can't convert usd amount")
+}
+
+pub fn decimal_to_usd(amount: Decimal) -> anyhow::Result<i128> {
+    amount
+        .checked_mul(Decimal::TEN.powu(USD_DECIMALS as u64))
+        .and_then(|amount| amount.trunc().to_i128())
+        .context("Can't convert usd amount")
+}
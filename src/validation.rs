@@ -0,0 +1,77 @@
+use anchor_lang::Discriminator;
+use anyhow::anyhow;
+use jupiter_amm_interface::AccountMap;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::AccountValidationError;
+
+pub fn get_account<'a>(account_map: &'a AccountMap, key: &Pubkey) -> anyhow::Result<&'a Account> {
+    account_map
+        .get(key)
+        .ok_or_else(|| anyhow!("Could not find address: {key}"))
+}
+
+pub fn validate_owner(
+    key: &Pubkey,
+    account: &Account,
+    expected: &Pubkey,
+) -> Result<(), AccountValidationError> {
+    if account.owner != *expected {
+        return Err(AccountValidationError::InvalidOwner {
+            account: *key,
+            expected: *expected,
+            actual: account.owner,
+        });
+    }
+
+    Ok(())
+}
+
+/// Accounts can be reallocated to grow, so only a minimum size is enforced.
+pub fn validate_size(
+    key: &Pubkey,
+    account: &Account,
+    expected: usize,
+) -> Result<(), AccountValidationError> {
+    if account.data.len() < expected {
+        return Err(AccountValidationError::InvalidSize {
+            account: *key,
+            expected,
+            actual: account.data.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks owner, discriminator and size of a zero-copy Adrena account before it
+/// is handed to `try_deserialize`, which would otherwise panic on a short buffer.
+pub fn validate_program_account<T: Discriminator>(
+    key: &Pubkey,
+    account: &Account,
+    program_id: &Pubkey,
+) -> Result<(), AccountValidationError> {
+    validate_owner(key, account, program_id)?;
+    validate_size(key, account, 8 + std::mem::size_of::<T>())?;
+
+    if account.data[..8] != T::DISCRIMINATOR {
+        return Err(AccountValidationError::InvalidDiscriminator { account: *key });
+    }
+
+    Ok(())
+}
+
+pub fn validate_oracle_owner(
+    key: &Pubkey,
+    account: &Account,
+    allowed_owners: &[Pubkey],
+) -> Result<(), AccountValidationError> {
+    if !allowed_owners.contains(&account.owner) {
+        return Err(AccountValidationError::InvalidOracleOwner {
+            account: *key,
+            actual: account.owner,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use common::usdc_wsol_pool;
+use jupiter_adrena::{AccountSource, FixtureAccountSource, PoolAmm};
+use jupiter_amm_interface::Amm;
+
+#[test]
+fn test() {
+    let fixture = usdc_wsol_pool();
+
+    let in_memory = fixture.source();
+    let expected = fixture.load().unwrap();
+
+    // Fixture directory
+    let dir = std::env::temp_dir().join(format!("jupiter-adrena-{}", fixture.pool_key));
+    let fixtures = FixtureAccountSource::new(&dir);
+    fixtures.write_accounts(&in_memory.accounts).unwrap();
+
+    let from_fixtures = PoolAmm::load(&fixtures, fixture.pool_key, None).unwrap();
+    assert_eq!(
+        from_fixtures.snapshot().unwrap().to_bytes().unwrap(),
+        expected.snapshot().unwrap().to_bytes().unwrap()
+    );
+
+    // Missing accounts are left out rather than failing the whole batch
+    let missing = solana_sdk::pubkey::Pubkey::new_unique();
+    assert!(fixtures.get_accounts(&[missing]).unwrap().is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // Local mock JSON-RPC server
+    #[cfg(feature = "rpc")]
+    {
+        let url = mock_rpc::serve(in_memory.accounts.clone());
+        let rpc = jupiter_adrena::RpcAccountSource::new(url);
+
+        let from_rpc = PoolAmm::load(&rpc, fixture.pool_key, None).unwrap();
+        assert_eq!(
+            from_rpc.snapshot().unwrap().to_bytes().unwrap(),
+            expected.snapshot().unwrap().to_bytes().unwrap()
+        );
+        assert_eq!(from_rpc.get_reserve_mints().len(), 3);
+    }
+}
+
+#[cfg(feature = "rpc")]
+mod mock_rpc {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        str::FromStr,
+        sync::Arc,
+        thread,
+    };
+
+    use base64::{engine::general_purpose, Engine};
+    use serde_json::{json, Value};
+    use solana_sdk::{account::Account, pubkey::Pubkey};
+
+    /// Serves `getMultipleAccounts` from `accounts` on a random local port and
+    /// returns its URL.
+    pub fn serve(accounts: HashMap<Pubkey, Account>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let accounts = Arc::new(accounts);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let accounts = accounts.clone();
+                thread::spawn(move || handle(stream, &accounts));
+            }
+        });
+
+        url
+    }
+
+    fn handle(stream: TcpStream, accounts: &HashMap<Pubkey, Account>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut stream = stream;
+
+        loop {
+            let mut content_length = 0;
+
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let request: Value = serde_json::from_slice(&body).unwrap();
+            let response = serde_json::to_vec(&respond(&request, accounts)).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                response.len()
+            )
+            .unwrap();
+            stream.write_all(&response).unwrap();
+        }
+    }
+
+    fn respond(request: &Value, accounts: &HashMap<Pubkey, Account>) -> Value {
+        assert_eq!(request["method"], "getMultipleAccounts");
+
+        let value: Vec<Value> = request["params"][0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| {
+                let key = Pubkey::from_str(key.as_str().unwrap()).unwrap();
+
+                match accounts.get(&key) {
+                    Some(account) => json!({
+                        "lamports": account.lamports,
+                        "data": [general_purpose::STANDARD.encode(&account.data), "base64"],
+                        "owner": account.owner.to_string(),
+                        "executable": account.executable,
+                        "rentEpoch": account.rent_epoch,
+                        "space": account.data.len(),
+                    }),
+                    None => Value::Null,
+                }
+            })
+            .collect();
+
+        json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "context": { "slot": 1 }, "value": value },
+        })
+    }
+}
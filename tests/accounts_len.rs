@@ -0,0 +1,87 @@
+mod common;
+
+use adrena::state::oracle::OracleType;
+use common::{
+    pyth_price_update_v2_account, token_2022_mint_account, usdc_wsol_pool, PROGRAM_ID,
+    SPL_TOKEN_ID, TOKEN_2022_ID, USDC, WSOL,
+};
+use jupiter_adrena::oracle::{PYTH_PUSH_ORACLE_ID, PYTH_RECEIVER_ID};
+use jupiter_amm_interface::{Amm, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    let amm = fixture.load().unwrap();
+    let owner = Pubkey::new_unique();
+    let user_token_accounts = amm.get_user_token_accounts(&owner);
+
+    let mut max_len = 0;
+
+    for (source_mint, destination_mint) in [
+        (USDC, WSOL),
+        (USDC, fixture.lp_token_mint),
+        (fixture.lp_token_mint, WSOL),
+    ] {
+        let metas = amm
+            .get_swap_and_account_metas(&SwapParams {
+                in_amount: 1_000_000,
+                out_amount: 0,
+                source_mint,
+                destination_mint,
+                source_token_account: user_token_accounts[&source_mint],
+                destination_token_account: user_token_accounts[&destination_mint],
+                token_transfer_authority: owner,
+                open_order_address: None,
+                quote_mint_to_referrer: None,
+                jupiter_program_id: &Pubkey::default(),
+                missing_dynamic_accounts_as_default: false,
+            })
+            .unwrap()
+            .account_metas;
+
+        let operation = amm.get_operation(&source_mint, &destination_mint);
+
+        assert_eq!(amm.get_accounts_len_for(operation), metas.len());
+        max_len = max_len.max(metas.len());
+    }
+
+    assert_eq!(amm.get_accounts_len(), max_len);
+
+    let programs: Vec<Pubkey> = amm
+        .program_dependencies()
+        .into_iter()
+        .map(|(program_id, _)| program_id)
+        .collect();
+
+    assert_eq!(programs, vec![PROGRAM_ID, SPL_TOKEN_ID]);
+
+    // Pyth oracles of both owners and a Token-2022 mint, named and sorted
+    for (mint, owner) in [(USDC, PYTH_RECEIVER_ID), (WSOL, PYTH_PUSH_ORACLE_ID)] {
+        let custody = fixture.custody_mut(&mint);
+        custody.oracle.oracle_type = OracleType::Pyth as u8;
+        let oracle_key = custody.oracle.oracle_account;
+
+        let mut oracle = pyth_price_update_v2_account(100_000_000, -8);
+        oracle.owner = owner;
+        fixture.accounts.insert(oracle_key, oracle);
+    }
+    fixture
+        .accounts
+        .insert(WSOL, token_2022_mint_account(9, None));
+
+    let amm = fixture.load().unwrap();
+    let programs: Vec<(Pubkey, String)> = amm.program_dependencies();
+
+    assert_eq!(
+        programs,
+        vec![
+            (PROGRAM_ID, "adrena".to_string()),
+            (PYTH_PUSH_ORACLE_ID, "pyth_push_oracle".to_string()),
+            (PYTH_RECEIVER_ID, "pyth_solana_receiver".to_string()),
+            (SPL_TOKEN_ID, "spl_token".to_string()),
+            (TOKEN_2022_ID, "spl_token_2022".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use common::{mint_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{ArbitragePath, LpArbitrage, PoolAmm};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use solana_sdk::pubkey::Pubkey;
+
+fn out_amount(amm: &PoolAmm, input_mint: Pubkey, output_mint: Pubkey, amount: u64) -> u64 {
+    amm.quote(&QuoteParams {
+        amount,
+        input_mint,
+        output_mint,
+        swap_mode: SwapMode::ExactIn,
+    })
+    .unwrap()
+    .out_amount
+}
+
+/// Holds $1M of USDC and $145k of SOL, backing `lp_supply` ALP.
+fn load(swap_fee: u16, liquidity_fee: u16, lp_supply: u64) -> (PoolAmm, Pubkey) {
+    let mut fixture = usdc_wsol_pool();
+    fixture
+        .accounts
+        .insert(fixture.lp_token_mint, mint_account(6, lp_supply));
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = swap_fee;
+        custody.fees.swap_out = swap_fee;
+        custody.fees.add_liquidity = liquidity_fee;
+        custody.fees.remove_liquidity = liquidity_fee;
+    }
+
+    (fixture.load().unwrap(), fixture.lp_token_mint)
+}
+
+/// Whether the ALP round trip pays out more than the direct swap.
+fn round_trip_cheaper(amm: &PoolAmm, lp_token_mint: Pubkey, amount: u64) -> bool {
+    let lp_amount = out_amount(amm, WSOL, lp_token_mint, amount);
+    out_amount(amm, lp_token_mint, USDC, lp_amount) > out_amount(amm, WSOL, USDC, amount)
+}
+
+fn sol_to_usdc(arbitrages: &[LpArbitrage]) -> &LpArbitrage {
+    arbitrages
+        .iter()
+        .find(|a| (a.input_mint, a.output_mint) == (WSOL, USDC))
+        .unwrap()
+}
+
+#[test]
+fn test() {
+    let sizes_usd = [100_000_000, 10_000_000_000, 1_000_000_000_000];
+
+    // Liquidity fees well below swap fees make the round trip cheaper
+    let (amm, lp_token_mint) = load(30, 10, 1_000_000_000_000);
+    let arbitrages = amm.lp_arbitrage(&sizes_usd).unwrap();
+    assert_eq!(arbitrages.len(), 2);
+
+    let sol_to_usdc = sol_to_usdc(&arbitrages);
+    assert_eq!(sol_to_usdc.samples.len(), sizes_usd.len());
+    assert_eq!(sol_to_usdc.cheaper_path, Some(ArbitragePath::LpRoundTrip));
+
+    // $100 of SOL
+    let sample = &sol_to_usdc.samples[0];
+    assert_eq!(sample.amount, 689_655_172);
+    assert_eq!(
+        sample.direct_out_amount,
+        Some(out_amount(&amm, WSOL, USDC, sample.amount))
+    );
+    let lp_amount = out_amount(&amm, WSOL, lp_token_mint, sample.amount);
+    assert_eq!(sample.lp_amount, Some(lp_amount));
+    assert_eq!(
+        sample.round_trip_out_amount,
+        Some(out_amount(&amm, lp_token_mint, USDC, lp_amount))
+    );
+    assert!(sample.round_trip_advantage_pct.unwrap() > rust_decimal::Decimal::ZERO);
+
+    // With one ALP unit worth $1, rounding the minted ALP down costs the round
+    // trip up to $1, more than it saves on fees below a few hundred dollars
+    let (amm, lp_token_mint) = load(30, 10, 1_145_000);
+    let arbitrages = amm.lp_arbitrage(&[100_000_000, 10_000_000_000]).unwrap();
+    let sol_to_usdc = sol_to_usdc(&arbitrages);
+    assert_eq!(sol_to_usdc.cheaper_path, Some(ArbitragePath::DirectSwap));
+    assert_eq!(
+        sol_to_usdc.samples[1].cheaper_path,
+        Some(ArbitragePath::LpRoundTrip)
+    );
+
+    // The search lands right on the size where the direct swap stops being
+    // cheaper
+    let amount = sol_to_usdc
+        .stops_being_cheaper_at(ArbitragePath::DirectSwap)
+        .expect("The direct swap stops being cheaper between $100 and $10k");
+    assert!(amount > sol_to_usdc.samples[0].amount);
+    assert!(amount <= sol_to_usdc.samples[1].amount);
+    assert!(!round_trip_cheaper(&amm, lp_token_mint, amount - 1));
+    assert!(round_trip_cheaper(&amm, lp_token_mint, amount));
+
+    // Rounding can hand the lead back and forth before the round trip keeps
+    // it, every change is reported where it happens
+    let crossovers = &sol_to_usdc.crossovers;
+    assert_eq!(crossovers[0].amount, amount);
+    assert_eq!(
+        crossovers.last().unwrap().to,
+        Some(ArbitragePath::LpRoundTrip)
+    );
+    for crossover in crossovers {
+        let round_trip = crossover.to == Some(ArbitragePath::LpRoundTrip);
+        assert_eq!(
+            round_trip_cheaper(&amm, lp_token_mint, crossover.amount - 1),
+            !round_trip
+        );
+        assert_eq!(
+            round_trip_cheaper(&amm, lp_token_mint, crossover.amount),
+            round_trip
+        );
+    }
+    for window in crossovers.windows(2) {
+        assert!(window[0].amount < window[1].amount);
+        assert_eq!(window[0].to, window[1].from);
+    }
+    assert_eq!(
+        sol_to_usdc.stops_being_cheaper_at(ArbitragePath::LpRoundTrip),
+        crossovers
+            .iter()
+            .find(|c| c.from == Some(ArbitragePath::LpRoundTrip))
+            .map(|c| c.amount)
+    );
+
+    // Expensive liquidity makes the direct swap cheaper at every size
+    let (amm, _) = load(30, 200, 1_000_000_000_000);
+    for arbitrage in amm.lp_arbitrage(&sizes_usd).unwrap() {
+        assert_eq!(arbitrage.cheaper_path, Some(ArbitragePath::DirectSwap));
+        assert!(arbitrage.crossovers.is_empty());
+        assert_eq!(
+            arbitrage.stops_being_cheaper_at(ArbitragePath::DirectSwap),
+            None
+        );
+    }
+
+    assert!(amm.lp_arbitrage(&[]).is_err());
+    assert!(amm.lp_arbitrage(&[2, 1]).is_err());
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::{clock_account, custom_oracle_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::PoolAmm;
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use rust_decimal::Decimal;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    // 1000 SOL, and $1000 of longs an hour after the last borrow rate update at
+    // 0.1% per hour, with $2 of fees settled at their last update
+    let custody = fixture.custody_mut(&WSOL);
+    custody.assets.owned = 1_000_000_000_000;
+    custody.long_positions.size_usd = 1_000_000_000;
+    custody.long_positions.cumulative_interest_usd = 2_000_000;
+    custody.borrow_rate_state.current_rate = 1_000_000;
+
+    let mut source = fixture.source();
+    let (clock_key, clock) = clock_account(3_600);
+    source.accounts.insert(clock_key, clock);
+    let amm = PoolAmm::load(&source, fixture.pool_key, None).unwrap();
+
+    // $145,000 of SOL and $3 of borrow fees, against nothing written by the
+    // program yet
+    let drift = amm.aum_drift().unwrap();
+    assert_eq!(drift.live_aum_usd, 145_003_000_000);
+    assert_eq!(drift.stored_aum_usd, 0);
+    assert_eq!(drift.drift_usd, 145_003_000_000);
+    assert_eq!(drift.drift_pct, Decimal::ZERO);
+    assert_eq!(amm.lp_nav_breakdown().unwrap().aum_usd, drift.live_aum_usd);
+    assert_eq!(amm.aum_error(), None);
+
+    // A custody whose value overflows can't be priced live, the pool still
+    // updates and falls back to the stored AUM, which ALP quotes report
+    fixture.custody_mut(&WSOL).assets.owned = u64::MAX;
+    fixture.accounts.insert(
+        fixture.custodies[1].1.oracle.oracle_account,
+        custom_oracle_account(u64::MAX, -8),
+    );
+    let amm = fixture.load().unwrap();
+    assert!(amm.live_aum_usd().is_err());
+    assert_eq!(amm.stored_aum_usd(), 0);
+    assert_eq!(amm.lp_price_usd().unwrap(), Some(Decimal::ZERO));
+    assert!(amm.aum_error().is_some());
+    assert!(amm.aum_drift().is_err());
+
+    let err = amm
+        .quote(&QuoteParams {
+            amount: 1_000_000,
+            input_mint: USDC,
+            output_mint: fixture.lp_token_mint,
+            swap_mode: SwapMode::ExactIn,
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("stored AUM"), "{err}");
+
+    let snapshot = amm.snapshot().unwrap();
+    let restored = PoolAmm::from_snapshot(&snapshot).unwrap();
+    assert_eq!(restored.aum_error(), amm.aum_error());
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::{custom_oracle_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{
+    Backtest, BacktestReport, BacktestRowKind, BacktestStep, ExecutionRecord, Operation, PoolAmm,
+};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use solana_sdk::pubkey::Pubkey;
+
+fn sol_to_usdc() -> QuoteParams {
+    QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: WSOL,
+        output_mint: USDC,
+        swap_mode: SwapMode::ExactIn,
+    }
+}
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = 30;
+        custody.fees.swap_out = 30;
+    }
+
+    let (usdc_custody, _) = fixture.custodies[0];
+    let (sol_custody, sol) = fixture.custodies[1];
+
+    let first = fixture.source().accounts;
+    let mut second = first.clone();
+    second.insert(
+        sol.oracle.oracle_account,
+        custom_oracle_account(15_000_000_000, -8),
+    );
+
+    let first_amm = fixture.load().unwrap();
+    let expected_first = first_amm.quote(&sol_to_usdc()).unwrap();
+    let expected_round_trip = first_amm.compare_paths(WSOL, USDC, 1_000_000_000).unwrap();
+    assert!(expected_round_trip.round_trip_out_amount.is_some());
+    let expected_second = PoolAmm::load(
+        &jupiter_adrena::InMemoryAccountSource::new(second.clone()),
+        fixture.pool_key,
+        None,
+    )
+    .unwrap()
+    .quote(&sol_to_usdc())
+    .unwrap();
+    assert!(expected_second.out_amount > expected_first.out_amount);
+
+    let unknown_mint = Pubkey::new_unique();
+    let backtest = Backtest::new(
+        fixture.pool_key,
+        None,
+        vec![
+            sol_to_usdc(),
+            QuoteParams {
+                amount: 1_000_000,
+                input_mint: USDC,
+                output_mint: unknown_mint,
+                swap_mode: SwapMode::ExactIn,
+            },
+        ],
+    );
+
+    let step = |slot: u64, accounts, executions| BacktestStep {
+        slot,
+        epoch: 0,
+        unix_timestamp: 1_715_000_000 + slot as i64,
+        accounts,
+        executions,
+    };
+
+    let report = backtest
+        .run([
+            step(100, first.clone(), vec![]),
+            step(
+                200,
+                second,
+                vec![ExecutionRecord {
+                    operation: Operation::Swap,
+                    owner: Pubkey::new_unique(),
+                    custody_in: Some(sol_custody),
+                    custody_out: Some(usdc_custody),
+                    in_amount: 1_000_000_000,
+                    out_amount: expected_second.out_amount - 1_000,
+                    fee_in: 0,
+                    fee_out: 0,
+                    price_in: None,
+                    price_out: None,
+                }],
+            ),
+            step(
+                300,
+                first.clone(),
+                vec![ExecutionRecord {
+                    operation: Operation::Swap,
+                    owner: Pubkey::new_unique(),
+                    custody_in: Some(Pubkey::new_unique()),
+                    custody_out: Some(usdc_custody),
+                    in_amount: 1_000_000_000,
+                    out_amount: 1,
+                    fee_in: 0,
+                    fee_out: 0,
+                    price_in: None,
+                    price_out: None,
+                }],
+            ),
+        ])
+        .unwrap();
+
+    assert_eq!(report.rows.len(), 8);
+
+    // Each step quotes against its own accounts
+    assert_eq!(report.rows[0].slot, 100);
+    assert_eq!(report.rows[0].out_amount, Some(expected_first.out_amount));
+    assert_eq!(report.rows[2].slot, 200);
+    assert_eq!(report.rows[2].out_amount, Some(expected_second.out_amount));
+    assert!(report.rows[2].aum_usd > report.rows[0].aum_usd);
+
+    // Swaps are compared with minting and redeeming ALP
+    assert_eq!(
+        report.rows[0].round_trip_out_amount,
+        expected_round_trip.round_trip_out_amount
+    );
+    assert_eq!(
+        report.rows[0].round_trip_advantage_pct,
+        expected_round_trip.round_trip_advantage_pct
+    );
+
+    // A failing quote is reported without stopping the run, along with the
+    // round trip failing for the same reason
+    let unknown_route = QuoteParams {
+        amount: 1_000_000,
+        input_mint: USDC,
+        output_mint: unknown_mint,
+        swap_mode: SwapMode::ExactIn,
+    };
+    assert_eq!(report.rows[1].out_amount, None);
+    assert_eq!(
+        report.rows[1].error,
+        Some(first_amm.quote(&unknown_route).unwrap_err().to_string())
+    );
+    assert!(report.rows[1].round_trip_error.is_some());
+
+    let execution = &report.rows[4];
+    assert_eq!(execution.kind, BacktestRowKind::Execution);
+    assert_eq!((execution.input_mint, execution.output_mint), (WSOL, USDC));
+    assert_eq!(execution.out_amount, Some(expected_second.out_amount));
+    assert_eq!(
+        execution.executed_out_amount,
+        Some(expected_second.out_amount - 1_000)
+    );
+    assert_eq!(execution.out_amount_diff, Some(-1_000));
+    assert!(execution.error.is_none());
+    assert!(execution.round_trip_out_amount.is_some());
+    assert!(execution.round_trip_error.is_none());
+
+    // An execution against a custody the pool doesn't have is reported too
+    let unknown_custody = &report.rows[7];
+    assert_eq!(unknown_custody.kind, BacktestRowKind::Execution);
+    assert_eq!(unknown_custody.slot, 300);
+    assert_eq!(unknown_custody.out_amount, None);
+    assert!(unknown_custody
+        .error
+        .as_ref()
+        .unwrap()
+        .contains("Custody does not exist"));
+    assert_eq!(report.rows[5].out_amount, Some(expected_first.out_amount));
+
+    let csv = report.to_csv().unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0], BacktestReport::CSV_HEADER);
+    assert!(lines[1].starts_with(&format!("100,1715000100,quote,{WSOL},{USDC},1000000000,")));
+    assert!(lines[2].ends_with('"'));
+    assert!(lines[5].contains(",execution,"));
+
+    // Replaying history backwards is a mistake in the dataset
+    assert!(backtest
+        .run([step(200, first.clone(), vec![]), step(100, first, vec![])])
+        .is_err());
+}
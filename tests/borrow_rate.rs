@@ -0,0 +1,59 @@
+mod common;
+
+use common::{usdc_wsol_pool, USDC, WSOL};
+use rust_decimal::Decimal;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    let custody = fixture.custody_mut(&WSOL);
+    custody.assets.owned = 1_000_000_000_000;
+    custody.assets.locked = 400_000_000_000;
+    // 0.01% base, +0.1% up to 80% utilization, +1% from there to 100%
+    custody.borrow_rate.base_rate = 100_000;
+    custody.borrow_rate.slope1 = 1_000_000;
+    custody.borrow_rate.slope2 = 10_000_000;
+    custody.borrow_rate.optimal_utilization = 800_000_000;
+    custody.borrow_rate_state.current_rate = 500_000;
+
+    let amm = fixture.load().unwrap();
+
+    let rate = amm.borrow_rate(WSOL).unwrap();
+    assert_eq!(rate.utilization_pct, Decimal::from(40));
+    assert_eq!(rate.hourly_rate_pct, Decimal::new(6, 2));
+    assert_eq!(rate.stored_hourly_rate_pct, Decimal::new(5, 2));
+
+    // An empty custody has nothing locked
+    let rates = amm.borrow_rates().unwrap();
+    assert_eq!(rates.len(), 2);
+    assert_eq!(rates[0].mint, USDC);
+    assert_eq!(rates[0].utilization_pct, Decimal::ZERO);
+
+    // Redeeming half of the SOL doubles the utilization, up to the kink
+    let after = amm.borrow_rate_after_redeem(WSOL, 500_000_000_000).unwrap();
+    assert_eq!(after.utilization_pct, Decimal::from(80));
+    assert_eq!(after.hourly_rate_pct, Decimal::new(11, 2));
+    assert!(amm
+        .borrow_rate_after_redeem(WSOL, 2_000_000_000_000)
+        .is_err());
+
+    let projection = amm.project_interest(WSOL, 24 * 3_600).unwrap();
+    assert_eq!(projection.accrued_pct, Decimal::new(144, 2));
+    assert_eq!(projection.cumulative_interest, 14_400_000);
+
+    let curve = amm.utilization_curve(WSOL, 6).unwrap();
+    let expected = [
+        (0, Decimal::new(1, 2)),
+        (20, Decimal::new(35, 3)),
+        (40, Decimal::new(6, 2)),
+        (60, Decimal::new(85, 3)),
+        (80, Decimal::new(11, 2)),
+        (100, Decimal::new(111, 2)),
+    ];
+    assert_eq!(curve.len(), expected.len());
+    for (point, (utilization_pct, hourly_rate_pct)) in curve.iter().zip(expected) {
+        assert_eq!(point.utilization_pct, Decimal::from(utilization_pct));
+        assert_eq!(point.hourly_rate_pct, hourly_rate_pct);
+    }
+}
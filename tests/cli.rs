@@ -0,0 +1,103 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::{env, process::Command};
+
+use common::{usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{AccountSource, FixtureAccountSource};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = 30;
+        custody.fees.swap_out = 30;
+    }
+
+    let dir = env::temp_dir().join(format!("jupiter-adrena-cli-{}", Pubkey::new_unique()));
+    let source = fixture.source();
+    FixtureAccountSource::new(&dir)
+        .write_accounts(&source.accounts)
+        .unwrap();
+    let mut amm = fixture.load().unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_jupiter-adrena"))
+            .arg("--fixtures")
+            .arg(&dir)
+            .args(["--pool", &fixture.pool_key.to_string()])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let quote = amm
+        .quote(&QuoteParams {
+            amount: 1_000_000_000,
+            input_mint: WSOL,
+            output_mint: USDC,
+            swap_mode: SwapMode::ExactIn,
+        })
+        .unwrap();
+    let output = run(&[
+        "quote",
+        "--input",
+        &WSOL.to_string(),
+        "--output",
+        &USDC.to_string(),
+        "--amount",
+        "1000000000",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("out_amount: {},", quote.out_amount)));
+    assert!(stdout.contains(&format!("fee_amount: {},", quote.fee_amount)));
+
+    // Exact out quoting isn't supported, rather than silently quoted exact in
+    let output = run(&[
+        "quote",
+        "--input",
+        &WSOL.to_string(),
+        "--output",
+        &USDC.to_string(),
+        "--amount",
+        "1000000000",
+        "--mode",
+        "exact-out",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Exact out quotes are not supported"));
+
+    // Both update phases, each under its own heading
+    let mut expected = String::new();
+    for phase in ["custodies", "oracles and tokens"] {
+        let keys = amm.get_accounts_to_update();
+        expected.push_str(&format!("{phase}:\n"));
+        for key in &keys {
+            expected.push_str(&format!("  {key}\n"));
+        }
+        amm.update(&source.get_accounts(&keys).unwrap()).unwrap();
+    }
+
+    let output = run(&["accounts"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+
+    // A pool missing from the fixtures is an error, not an empty listing
+    let output = Command::new(env!("CARGO_BIN_EXE_jupiter-adrena"))
+        .arg("--fixtures")
+        .arg(&dir)
+        .args(["--pool", &Pubkey::new_unique().to_string(), "accounts"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,291 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use adrena::state::{
+    cortex::Cortex,
+    custody::Custody,
+    oracle::{CustomOracle, OracleType},
+    pool::Pool,
+};
+use anchor_lang::{solana_program::hash::hash, AccountSerialize, Discriminator};
+use jupiter_adrena::{
+    oracle::{PYTH_PRICE_UPDATE_V2_LEN, PYTH_RECEIVER_ID},
+    InMemoryAccountSource, PoolAmm,
+};
+use solana_sdk::{account::Account, clock::Clock, pubkey, pubkey::Pubkey, sysvar};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensionsMut, ExtensionType,
+        StateWithExtensionsMut,
+    },
+    solana_program::{program_option::COption, program_pack::Pack},
+    state::Mint,
+};
+
+pub use adrena::ID as PROGRAM_ID;
+pub const SPL_TOKEN_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const USDC: Pubkey = pubkey!("3jdYcGYZaQVvcvMQGqVpt37JegEoDDnX7k4gSGAeGRqG");
+pub const WSOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+pub struct CustodyFixture {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub price: u64,
+    pub exponent: i32,
+}
+
+/// A pool built from zeroed program accounts, with one custom oracle and one SPL
+/// mint per custody, that can be loaded into a `PoolAmm` without any RPC.
+pub struct PoolFixture {
+    pub pool_key: Pubkey,
+    pub lp_token_mint: Pubkey,
+    pub pool: Pool,
+    pub custodies: Vec<(Pubkey, Custody)>,
+    pub cortex: Cortex,
+    pub accounts: HashMap<Pubkey, Account>,
+}
+
+impl PoolFixture {
+    pub fn new(custodies: &[CustodyFixture]) -> Self {
+        let pool_key = Pubkey::new_unique();
+        let (lp_token_mint, lp_token_bump) =
+            Pubkey::find_program_address(&[b"lp_token_mint", pool_key.as_ref()], &PROGRAM_ID);
+
+        let mut pool: Pool = bytemuck::Zeroable::zeroed();
+        pool.lp_token_bump = lp_token_bump;
+
+        let mut accounts = HashMap::new();
+        accounts.insert(lp_token_mint, mint_account(6, 1_000_000_000_000));
+
+        let mut cortex: Cortex = bytemuck::Zeroable::zeroed();
+        cortex.lp_staking_fee_share_bps = 7_000;
+        cortex.lm_staking_fee_share_bps = 2_000;
+        cortex.protocol_fee_share_bps = 1_000;
+        cortex.referrer_fee_share_bps = 500;
+
+        let custodies = custodies
+            .iter()
+            .enumerate()
+            .map(|(i, fixture)| {
+                let custody_key = pda(&[b"custody", pool_key.as_ref(), fixture.mint.as_ref()]);
+                let oracle_key = Pubkey::new_unique();
+
+                let mut custody: Custody = bytemuck::Zeroable::zeroed();
+                custody.pool = pool_key;
+                custody.mint = fixture.mint;
+                custody.decimals = fixture.decimals;
+                custody.token_account = pda(&[
+                    b"custody_token_account",
+                    pool_key.as_ref(),
+                    fixture.mint.as_ref(),
+                ]);
+                custody.oracle.oracle_account = oracle_key;
+                custody.oracle.oracle_type = OracleType::Custom as u8;
+
+                pool.custodies[i] = custody_key;
+
+                accounts.insert(fixture.mint, mint_account(fixture.decimals, 0));
+                accounts.insert(
+                    oracle_key,
+                    custom_oracle_account(fixture.price, fixture.exponent),
+                );
+
+                (custody_key, custody)
+            })
+            .collect();
+
+        PoolFixture {
+            pool_key,
+            lp_token_mint,
+            pool,
+            custodies,
+            cortex,
+            accounts,
+        }
+    }
+
+    pub fn custody_mut(&mut self, mint: &Pubkey) -> &mut Custody {
+        &mut self
+            .custodies
+            .iter_mut()
+            .find(|(_, c)| c.mint == *mint)
+            .unwrap()
+            .1
+    }
+
+    /// Every account of the pool, as a chain would serve them.
+    pub fn source(&self) -> InMemoryAccountSource {
+        let mut account_map = self.accounts.clone();
+
+        account_map.insert(self.pool_key, program_account(&self.pool));
+        account_map.insert(pda(&[b"cortex"]), program_account(&self.cortex));
+        for (custody_key, custody) in &self.custodies {
+            account_map.insert(*custody_key, program_account(custody));
+        }
+
+        InMemoryAccountSource::new(account_map)
+    }
+
+    /// Runs the pool through `from_keyed_account` and both update phases.
+    pub fn load(&self) -> anyhow::Result<PoolAmm> {
+        PoolAmm::load(&self.source(), self.pool_key, None)
+    }
+}
+
+/// USDC at $1 and SOL at $145, the pool most tests run against.
+pub fn usdc_wsol_pool() -> PoolFixture {
+    PoolFixture::new(&[
+        CustodyFixture {
+            mint: USDC,
+            decimals: 6,
+            price: 100_000_000,
+            exponent: -8,
+        },
+        CustodyFixture {
+            mint: WSOL,
+            decimals: 9,
+            price: 14_500_000_000,
+            exponent: -8,
+        },
+    ])
+}
+
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+}
+
+pub fn program_account<T: bytemuck::Pod + Discriminator>(state: &T) -> Account {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(state));
+
+    Account {
+        lamports: 7_516_800,
+        data,
+        owner: PROGRAM_ID,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+pub fn mint_account(decimals: u8, supply: u64) -> Account {
+    let mut data = vec![0u8; Mint::LEN];
+
+    Mint::pack(
+        Mint {
+            mint_authority: COption::None,
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &mut data,
+    )
+    .unwrap();
+
+    Account {
+        lamports: 1_461_600,
+        data,
+        owner: SPL_TOKEN_ID,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+/// A Token-2022 mint, withholding `transfer_fee_bps` of every transfer when set.
+pub fn token_2022_mint_account(decimals: u8, transfer_fee_bps: Option<u16>) -> Account {
+    let extensions = match transfer_fee_bps {
+        Some(_) => vec![ExtensionType::TransferFeeConfig],
+        None => vec![],
+    };
+    let mut data =
+        vec![0u8; ExtensionType::try_calculate_account_len::<Mint>(&extensions).unwrap()];
+
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    if let Some(bps) = transfer_fee_bps {
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.newer_transfer_fee.transfer_fee_basis_points = bps.into();
+        config.newer_transfer_fee.maximum_fee = u64::MAX.into();
+    }
+    state.base = Mint {
+        mint_authority: COption::None,
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+
+    Account {
+        lamports: 1_461_600,
+        data,
+        owner: TOKEN_2022_ID,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+pub fn custom_oracle_account(price: u64, exponent: i32) -> Account {
+    let mut data = vec![];
+    CustomOracle {
+        price,
+        expo: exponent,
+        conf: 100,
+        ema: price,
+        publish_time: 1_715_000_000,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+
+    Account {
+        lamports: 1_197_120,
+        data,
+        owner: PROGRAM_ID,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+pub fn discriminator(name: &str) -> Vec<u8> {
+    hash(format!("account:{name}").as_bytes()).to_bytes()[..8].to_vec()
+}
+
+/// A Pyth receiver `PriceUpdateV2` account, fully verified.
+pub fn pyth_price_update_v2_account(price: i64, exponent: i32) -> Account {
+    let mut data = discriminator("PriceUpdateV2");
+    data.extend_from_slice(Pubkey::new_unique().as_ref()); // write_authority
+    data.push(1); // VerificationLevel::Full
+    data.extend_from_slice(&[7u8; 32]); // feed_id
+    data.extend_from_slice(&price.to_le_bytes());
+    data.extend_from_slice(&10_000u64.to_le_bytes()); // conf
+    data.extend_from_slice(&exponent.to_le_bytes());
+    data.extend_from_slice(&1_715_000_000i64.to_le_bytes()); // publish_time
+    data.extend_from_slice(&1_714_999_999i64.to_le_bytes()); // prev_publish_time
+    data.extend_from_slice(&price.to_le_bytes()); // ema_price
+    data.extend_from_slice(&10_000u64.to_le_bytes()); // ema_conf
+    data.extend_from_slice(&265_000_000u64.to_le_bytes()); // posted_slot
+    data.resize(PYTH_PRICE_UPDATE_V2_LEN, 0);
+
+    Account {
+        lamports: 1_231_920,
+        data,
+        owner: PYTH_RECEIVER_ID,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
+
+/// The clock sysvar, for sources that should not run at the Unix epoch.
+pub fn clock_account(unix_timestamp: i64) -> (Pubkey, Account) {
+    let clock = Clock {
+        unix_timestamp,
+        ..Clock::default()
+    };
+
+    (
+        sysvar::clock::id(),
+        Account::new_data(1_169_280, &clock, &sysvar::id()).unwrap(),
+    )
+}
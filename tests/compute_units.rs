@@ -0,0 +1,41 @@
+mod common;
+
+use common::{CustodyFixture, PoolFixture, USDC, WSOL};
+use jupiter_adrena::{estimate_compute_units, Operation, MAX_COMPUTE_UNITS};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let custody = |mint| CustodyFixture {
+        mint,
+        decimals: 6,
+        price: 100_000_000,
+        exponent: -8,
+    };
+
+    let small = PoolFixture::new(&[custody(USDC), custody(WSOL)])
+        .load()
+        .unwrap();
+    let large = PoolFixture::new(&[
+        custody(USDC),
+        custody(WSOL),
+        custody(Pubkey::new_unique()),
+        custody(Pubkey::new_unique()),
+        custody(Pubkey::new_unique()),
+    ])
+    .load()
+    .unwrap();
+
+    for operation in [
+        Operation::Swap,
+        Operation::AddLiquidity,
+        Operation::RemoveLiquidity,
+    ] {
+        let small_estimate = estimate_compute_units(operation, &small);
+        let large_estimate = estimate_compute_units(operation, &large);
+
+        // Every handler recomputes the AUM over every custody
+        assert!(small_estimate < large_estimate);
+        assert!(large_estimate <= MAX_COMPUTE_UNITS);
+    }
+}
@@ -0,0 +1,282 @@
+//! Runs the Adrena program against a dumped pool and checks that
+//! `estimate_compute_units` covers what every operation consumes.
+//!
+//! Needs `ADRENA_PROGRAM_SO`, the path of the program's `adrena.so`, and
+//! `ADRENA_POOL_ACCOUNTS`, a directory of `solana account --output json` dumps
+//! holding the pool, its custodies, oracles and mints, the staking and cortex
+//! accounts and the clock sysvar. `ADRENA_POOL` picks the pool, the devnet one
+//! by default. Ignored by default, run it with
+//! `cargo test --test compute_units_program -- --ignored`.
+
+use std::{collections::HashMap, env, path::Path, str::FromStr};
+
+use adrena::{
+    instruction,
+    instructions::{
+        AddLiquidityParams, RemoveLiquidityParams, SwapParams as SwapInstructionParams,
+    },
+};
+use anchor_lang::InstructionData;
+use jupiter_adrena::{
+    estimate_compute_units, AccountSource, FixtureAccountSource, Operation, PoolAmm,
+    MAX_COMPUTE_UNITS, NATIVE_MINT,
+};
+use jupiter_amm_interface::{Amm, SwapParams};
+use solana_program_test::ProgramTest;
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    program_option::COption,
+    pubkey,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::Transaction,
+};
+use spl_token_2022::{
+    extension::{
+        BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensions,
+        StateWithExtensionsMut,
+    },
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+
+const DEVNET_POOL: Pubkey = pubkey!("2buhqUduNw7wNhZ1ixFxfvLRX3gAZkGmg8G1Rv5SEur7");
+/// Rent exemption of a token account without extensions.
+const TOKEN_ACCOUNT_RENT: u64 = 2_039_280;
+
+#[tokio::test]
+#[ignore = "needs the program binary and dumped pool accounts"]
+async fn test() {
+    let program_so = env::var("ADRENA_PROGRAM_SO")
+        .expect("ADRENA_PROGRAM_SO must be the path of the program's adrena.so");
+    let accounts_dir = env::var("ADRENA_POOL_ACCOUNTS")
+        .expect("ADRENA_POOL_ACCOUNTS must be a directory of the pool's account dumps");
+    let pool_key = env::var("ADRENA_POOL")
+        .map(|key| Pubkey::from_str(&key).unwrap())
+        .unwrap_or(DEVNET_POOL);
+
+    let source = FixtureAccountSource::new(accounts_dir);
+    let amm = PoolAmm::load(&source, pool_key, None).unwrap();
+
+    let owner = Keypair::new();
+    let user_token_accounts = amm.get_user_token_accounts(&owner.pubkey());
+    let mints = amm.get_reserve_mints();
+    let mint_accounts = source
+        .get_accounts(&user_token_accounts.keys().copied().collect::<Vec<_>>())
+        .unwrap();
+    let decimals = |mint: &Pubkey| {
+        StateWithExtensions::<Mint>::unpack(&mint_accounts[mint].data)
+            .unwrap()
+            .base
+            .decimals
+    };
+
+    let swap_params = |source_mint: Pubkey, destination_mint: Pubkey, in_amount: u64| SwapParams {
+        in_amount,
+        out_amount: 0,
+        source_mint,
+        destination_mint,
+        source_token_account: user_token_accounts[&source_mint],
+        destination_token_account: user_token_accounts[&destination_mint],
+        token_transfer_authority: owner.pubkey(),
+        open_order_address: None,
+        quote_mint_to_referrer: None,
+        jupiter_program_id: &Pubkey::default(),
+        missing_dynamic_accounts_as_default: false,
+    };
+
+    // Every route between the pool's mints, ALP included, with one whole token in
+    let mut operations = vec![];
+    for source_mint in &mints {
+        for destination_mint in &mints {
+            if source_mint == destination_mint
+                || !amm.is_route_allowed(source_mint, destination_mint)
+            {
+                continue;
+            }
+
+            let params = swap_params(
+                *source_mint,
+                *destination_mint,
+                10u64.pow(decimals(source_mint) as u32),
+            );
+            let operation = amm.get_operation(source_mint, destination_mint);
+
+            let data = match operation {
+                Operation::Swap => instruction::Swap {
+                    params: SwapInstructionParams {
+                        amount_in: params.in_amount,
+                        min_amount_out: 0,
+                    },
+                }
+                .data(),
+                Operation::AddLiquidity => instruction::AddLiquidity {
+                    params: AddLiquidityParams {
+                        amount_in: params.in_amount,
+                        min_lp_amount_out: 0,
+                    },
+                }
+                .data(),
+                Operation::RemoveLiquidity => instruction::RemoveLiquidity {
+                    params: RemoveLiquidityParams {
+                        lp_amount_in: params.in_amount,
+                        min_amount_out: 0,
+                    },
+                }
+                .data(),
+            };
+
+            let instruction = Instruction {
+                program_id: amm.program_id(),
+                accounts: amm
+                    .get_swap_and_account_metas(&params)
+                    .unwrap()
+                    .account_metas,
+                data,
+            };
+
+            operations.push((operation, *source_mint, *destination_mint, instruction));
+        }
+    }
+
+    let program_dir = Path::new(&program_so).parent().unwrap();
+    env::set_var("SBF_OUT_DIR", program_dir);
+    let mut program_test = ProgramTest::new("adrena", amm.program_id(), None);
+    program_test.prefer_bpf(true);
+
+    let mut keys: Vec<Pubkey> = operations
+        .iter()
+        .flat_map(|(_, _, _, instruction)| instruction.accounts.iter().map(|meta| meta.pubkey))
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let accounts = source.get_accounts(&keys).unwrap();
+    for (key, account) in &accounts {
+        if !account.executable {
+            program_test.add_account(*key, account.clone());
+        }
+    }
+
+    program_test.add_account(
+        owner.pubkey(),
+        Account::new(100_000_000_000, 0, &system_program::id()),
+    );
+    for (mint, token_account) in &user_token_accounts {
+        program_test.add_account(
+            *token_account,
+            token_account_for(
+                mint,
+                &mint_accounts[mint],
+                &owner.pubkey(),
+                1_000 * 10u64.pow(decimals(mint) as u32),
+            ),
+        );
+    }
+
+    let mut context = program_test.start_with_context().await;
+    if let Some(clock) = source.get_clock().unwrap() {
+        context.set_sysvar(&clock);
+    }
+
+    let mut consumed_by_operation: HashMap<Operation, u64> = HashMap::new();
+
+    for (operation, source_mint, destination_mint, instruction) in operations {
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNITS),
+                instruction,
+            ],
+            Some(&owner.pubkey()),
+            &[&owner],
+            context.last_blockhash,
+        );
+
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        let details = simulation.simulation_details.unwrap();
+
+        assert!(
+            matches!(simulation.result, Some(Ok(()))),
+            "{source_mint} to {destination_mint} failed: {:?}\n{}",
+            simulation.result,
+            details.logs.join("\n")
+        );
+
+        let estimate = estimate_compute_units(operation, &amm) as u64;
+        assert!(
+            details.units_consumed <= estimate,
+            "{operation:?} from {source_mint} to {destination_mint} consumed {} units, over the {estimate} estimated",
+            details.units_consumed
+        );
+
+        let consumed = consumed_by_operation.entry(operation).or_default();
+        *consumed = (*consumed).max(details.units_consumed);
+    }
+
+    // What the constants in `compute_units.rs` are derived from
+    for (operation, consumed) in consumed_by_operation {
+        eprintln!(
+            "{operation:?} on {} custodies: at most {consumed} units consumed, {} estimated",
+            mints.len(),
+            estimate_compute_units(operation, &amm)
+        );
+    }
+}
+
+/// A token account of `owner` holding `amount`, with the extensions its mint
+/// requires.
+fn token_account_for(
+    mint: &Pubkey,
+    mint_account: &Account,
+    owner: &Pubkey,
+    amount: u64,
+) -> Account {
+    let extensions = if mint_account.owner == spl_token_2022::id() {
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        ExtensionType::get_required_init_account_extensions(
+            &mint_state.get_extension_types().unwrap(),
+        )
+    } else {
+        vec![]
+    };
+
+    let mut data =
+        vec![0u8; ExtensionType::try_calculate_account_len::<TokenAccount>(&extensions).unwrap()];
+    let mut state =
+        StateWithExtensionsMut::<TokenAccount>::unpack_uninitialized(&mut data).unwrap();
+    for extension in extensions {
+        state.init_account_extension_from_type(extension).unwrap();
+    }
+
+    let is_native = *mint == NATIVE_MINT;
+    state.base = TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: if is_native {
+            COption::Some(TOKEN_ACCOUNT_RENT)
+        } else {
+            COption::None
+        },
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+
+    Account {
+        lamports: TOKEN_ACCOUNT_RENT + if is_native { amount } else { 0 },
+        data,
+        owner: mint_account.owner,
+        executable: false,
+        rent_epoch: u64::MAX,
+    }
+}
@@ -1,10 +1,12 @@
-use jupiter_adrena::PoolAmm;
+use adrena::state::oracle::OracleType;
+use jupiter_adrena::{oracle, PoolAmm};
 use jupiter_amm_interface::{Amm, AmmContext, ClockRef, KeyedAccount, QuoteParams, SwapMode};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{account::Account, clock::Clock, pubkey as key, pubkey::Pubkey, sysvar::SysvarId};
 use std::collections::HashMap;
 
 #[test]
+#[ignore = "reads the devnet pool over RPC"]
 fn test() {
     let client = RpcClient::new("https://api.devnet.solana.com");
 
@@ -26,6 +28,7 @@ fn test() {
         (("SOL", "BONK"), 10_000_000_000),
         (("SOL", "BTC"), 10_000_000_000),
         (("SOL", "USDC"), 10_000_000_000),
+        (("SOL", "ALP"), 1_000_000_000),
     ]);
     let keyed_pool = KeyedAccount {
         account: pool_acc,
@@ -113,4 +116,15 @@ fn test() {
         .unwrap();
     println!("{quote:?}");
     println!("====================================");
+
+    // Sponsored SOL/USD feed of the Pyth push oracle
+    let feed = key!("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+    let price = oracle::parse_oracle_account(
+        OracleType::Pyth,
+        &feed,
+        &client.get_account(&feed).unwrap(),
+        &amm.program_id(),
+    )
+    .unwrap();
+    assert_eq!(price.exponent, -8);
 }
@@ -0,0 +1,132 @@
+//! Decodes the events of the devnet pool's latest transactions and checks them
+//! against the instructions that emitted them. Ignored by default, run it with
+//! `cargo test --test devnet_events -- --ignored`.
+
+use std::str::FromStr;
+
+use jupiter_adrena::{decode_instruction, parse_logs, InstructionArgs, Operation};
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, pubkey as key, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiInstruction, UiTransactionEncoding,
+};
+
+#[test]
+#[ignore = "reads the devnet pool's transactions over RPC"]
+fn test() {
+    let client = RpcClient::new("https://api.devnet.solana.com");
+    let pool_key = key!("2buhqUduNw7wNhZ1ixFxfvLRX3gAZkGmg8G1Rv5SEur7");
+    let program_id = adrena::ID;
+
+    let signatures = client
+        .get_signatures_for_address_with_config(
+            &pool_key,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(100),
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let mut decoded = 0;
+
+    for signature in signatures.iter().filter(|s| s.err.is_none()) {
+        let transaction = client
+            .get_transaction_with_config(
+                &Signature::from_str(&signature.signature).unwrap(),
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .unwrap()
+            .transaction;
+        let meta = transaction.meta.unwrap();
+
+        let OptionSerializer::Some(logs) = &meta.log_messages else {
+            continue;
+        };
+        let records = parse_logs(&program_id, logs).unwrap();
+
+        let message = transaction.transaction.decode().unwrap().message;
+        let mut account_keys = message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            account_keys.extend(
+                loaded
+                    .writable
+                    .iter()
+                    .chain(&loaded.readonly)
+                    .map(|key| Pubkey::from_str(key).unwrap()),
+            );
+        }
+
+        // Instructions in execution order, each followed by the ones it invoked
+        let mut instructions: Vec<(u8, Vec<u8>)> = vec![];
+        for (index, instruction) in message.instructions().iter().enumerate() {
+            instructions.push((instruction.program_id_index, instruction.data.clone()));
+
+            if let OptionSerializer::Some(inner) = &meta.inner_instructions {
+                for instruction in inner
+                    .iter()
+                    .filter(|inner| inner.index as usize == index)
+                    .flat_map(|inner| &inner.instructions)
+                {
+                    if let UiInstruction::Compiled(compiled) = instruction {
+                        instructions.push((
+                            compiled.program_id_index,
+                            bs58::decode(&compiled.data).into_vec().unwrap(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let args: Vec<InstructionArgs> = instructions
+            .iter()
+            .filter(|(program, _)| account_keys[*program as usize] == program_id)
+            .filter_map(|(_, data)| decode_instruction(data).unwrap())
+            .collect();
+
+        assert_eq!(records.len(), args.len(), "{}", signature.signature);
+
+        for (record, args) in records.iter().zip(args) {
+            let (operation, in_amount, min_out_amount) = match args {
+                InstructionArgs::Swap {
+                    amount_in,
+                    min_amount_out,
+                } => (Operation::Swap, amount_in, min_amount_out),
+                InstructionArgs::AddLiquidity {
+                    amount_in,
+                    min_lp_amount_out,
+                } => (Operation::AddLiquidity, amount_in, min_lp_amount_out),
+                InstructionArgs::RemoveLiquidity {
+                    lp_amount_in,
+                    min_amount_out,
+                } => (Operation::RemoveLiquidity, lp_amount_in, min_amount_out),
+            };
+
+            assert_eq!(record.operation, operation, "{}", signature.signature);
+            assert_eq!(record.in_amount, in_amount, "{}", signature.signature);
+            assert!(
+                record.out_amount >= min_out_amount,
+                "{}",
+                signature.signature
+            );
+        }
+
+        decoded += records.len();
+    }
+
+    assert!(
+        decoded > 0,
+        "No swap or liquidity event in the pool's last {} transactions",
+        signatures.len()
+    );
+}
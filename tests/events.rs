@@ -0,0 +1,146 @@
+use std::fs;
+
+use adrena::{events::SwapEvent, instruction, instructions::SwapParams};
+use anchor_lang::{Discriminator, InstructionData};
+use base64::{engine::general_purpose, Engine};
+use jupiter_adrena::{
+    decode_event, decode_instruction, parse_logs, ExecutionRecord, InstructionArgs, Operation,
+};
+use jupiter_amm_interface::Quote;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+const ADRENA: Pubkey = pubkey!("13gDzEXCdocbj8iAiqrScGo47NiSuYENGsRqi3SEAwet");
+const OWNER: Pubkey = pubkey!("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM");
+const SOL_CUSTODY: Pubkey = pubkey!("GZ9XfWwgTRhkma2Y91Q9r1XKotNXYjBnKKabj19rhT71");
+const USDC_CUSTODY: Pubkey = pubkey!("Dk523LZeDQbZtUwPEBjFXCd2Au1tD7mWZBJJmcgHktNk");
+
+/// `logMessages` of a transaction, out of `tests/fixtures/logs/<name>.json`,
+/// which holds the `meta` of a `getTransaction` result. The fixtures are laid
+/// out after the program's transactions rather than captured, a capture can
+/// replace one as is with `jq .result.meta`.
+fn logs(name: &str) -> Vec<String> {
+    let path = format!(
+        "{}/tests/fixtures/logs/{name}.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let meta: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+
+    serde_json::from_value(meta["logMessages"].clone()).unwrap()
+}
+
+#[test]
+fn test() {
+    let swap = ExecutionRecord {
+        operation: Operation::Swap,
+        owner: OWNER,
+        custody_in: Some(SOL_CUSTODY),
+        custody_out: Some(USDC_CUSTODY),
+        in_amount: 1_000_000_000,
+        out_amount: 144_565_000,
+        fee_in: 3_000_000,
+        fee_out: 433_700,
+        price_in: Some(1_450_000_000_000),
+        price_out: Some(10_000_000_000),
+    };
+
+    assert_eq!(
+        parse_logs(&ADRENA, &logs("swap")).unwrap(),
+        vec![swap.clone()]
+    );
+
+    // Invoked by the router, which logs a `SwapEvent` of its own afterwards
+    let router_swap = logs("router_swap");
+    assert_eq!(parse_logs(&ADRENA, &router_swap).unwrap(), vec![swap]);
+
+    assert_eq!(
+        parse_logs(&ADRENA, &logs("add_liquidity")).unwrap(),
+        vec![ExecutionRecord {
+            operation: Operation::AddLiquidity,
+            owner: OWNER,
+            custody_in: Some(USDC_CUSTODY),
+            custody_out: None,
+            in_amount: 100_000_000,
+            out_amount: 98_765_432,
+            fee_in: 200_000,
+            fee_out: 0,
+            price_in: Some(10_000_000_000),
+            price_out: None,
+        }]
+    );
+
+    assert_eq!(
+        parse_logs(&ADRENA, &logs("remove_liquidity")).unwrap(),
+        vec![ExecutionRecord {
+            operation: Operation::RemoveLiquidity,
+            owner: OWNER,
+            custody_in: None,
+            custody_out: Some(SOL_CUSTODY),
+            in_amount: 50_000_000,
+            out_amount: 339_000_000,
+            fee_in: 0,
+            fee_out: 1_020_000,
+            price_in: None,
+            price_out: Some(1_450_000_000_000),
+        }]
+    );
+
+    // Events of a reverted transaction didn't happen, even when logged before
+    // the error
+    assert!(parse_logs(&ADRENA, &logs("failed_swap"))
+        .unwrap()
+        .is_empty());
+
+    // Logs of another program id are skipped
+    assert!(parse_logs(&Pubkey::new_unique(), &router_swap)
+        .unwrap()
+        .is_empty());
+
+    let data = instruction::Swap {
+        params: SwapParams {
+            amount_in: 1_000_000_000,
+            min_amount_out: 144_000_000,
+        },
+    }
+    .data();
+    assert_eq!(
+        decode_instruction(&data).unwrap(),
+        Some(InstructionArgs::Swap {
+            amount_in: 1_000_000_000,
+            min_amount_out: 144_000_000,
+        })
+    );
+
+    let record = &parse_logs(&ADRENA, &router_swap).unwrap()[0];
+    let diff = record
+        .diff(&Quote {
+            in_amount: 1_000_000_000,
+            out_amount: 144_500_000,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(diff.out_amount_diff, 65_000);
+    assert_eq!(
+        diff.out_amount_diff_pct.round_dp(6),
+        Decimal::new(44_983, 6)
+    );
+
+    let data = router_swap
+        .iter()
+        .find_map(|log| log.strip_prefix("Program data: "))
+        .map(|data| general_purpose::STANDARD.decode(data).unwrap())
+        .unwrap();
+    assert_eq!(&data[..8], SwapEvent::DISCRIMINATOR);
+    assert_eq!(
+        decode_event(&data).unwrap().unwrap().out_amount,
+        144_565_000
+    );
+
+    // Unknown events and instructions are not an error, truncated ones are
+    assert_eq!(decode_event(&[0; 16]).unwrap(), None);
+    assert_eq!(decode_instruction(&[0; 16]).unwrap(), None);
+    assert!(decode_event(&data[..20]).is_err());
+    assert!(decode_event(&data[..4]).is_err());
+    assert!(decode_instruction(&[0; 4]).is_err());
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use adrena::state::user_profile::UserProfile;
+use common::{pda, program_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{FeeAttribution, FeeSplit, PoolAmm};
+use jupiter_amm_interface::{QuoteParams, SwapMode};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = 30;
+        custody.fees.swap_out = 30;
+        custody.fees.add_liquidity = 20;
+        custody.fees.remove_liquidity = 20;
+    }
+
+    let mut amm = fixture.load().unwrap();
+
+    // The split comes from the Cortex
+    let split = *amm.fee_split().unwrap();
+    assert_eq!(
+        split,
+        FeeSplit {
+            lp_staking_bps: 7_000,
+            lm_staking_bps: 2_000,
+            protocol_bps: 1_000,
+            referrer_bps: 500,
+        }
+    );
+
+    // Owners whose profile has a referrer pay it, others don't
+    let referred_owner = Pubkey::new_unique();
+    let unreferred_owner = Pubkey::new_unique();
+    for (owner, referrer) in [
+        (referred_owner, Pubkey::new_unique()),
+        (unreferred_owner, Pubkey::default()),
+    ] {
+        amm.load_user_profile(owner, Some(&profile_account(&owner, &referrer)))
+            .unwrap();
+    }
+
+    for (input_mint, output_mint) in [
+        (WSOL, USDC),
+        (WSOL, fixture.lp_token_mint),
+        (fixture.lp_token_mint, WSOL),
+    ] {
+        let params = QuoteParams {
+            amount: 1_000_000_000,
+            input_mint,
+            output_mint,
+            swap_mode: SwapMode::ExactIn,
+        };
+
+        let fees = amm.attribute_fees(&params, None).unwrap();
+        assert_eq!(fees.fee_mint, USDC);
+        assert_eq!(fees.referrer_amount, 0);
+        assert_split(&fees, &split, false);
+
+        let unreferred = amm
+            .attribute_fees(&params, Some(&unreferred_owner))
+            .unwrap();
+        assert_eq!(unreferred, fees);
+
+        // A referrer is paid out of the protocol share only
+        let referred = amm.attribute_fees(&params, Some(&referred_owner)).unwrap();
+        assert_eq!(referred.total_amount, fees.total_amount);
+        assert_eq!(referred.lp_staking_amount, fees.lp_staking_amount);
+        assert_eq!(referred.lm_staking_amount, fees.lm_staking_amount);
+        assert_eq!(
+            referred.protocol_amount + referred.referrer_amount,
+            fees.protocol_amount
+        );
+        assert_split(&referred, &split, true);
+    }
+
+    // Selling 1 SOL pays fees
+    let swap_fees = amm.attribute_fees(&sell_one_sol(), None).unwrap();
+    assert!(swap_fees.total_amount > 0);
+    // The reward token is USDC, one unit per micro dollar
+    assert_eq!(swap_fees.total_amount, swap_fees.total_usd);
+
+    // Unknown mints are not quoted
+    let mut unknown = sell_one_sol();
+    unknown.output_mint = Pubkey::new_unique();
+    assert!(amm.attribute_fees(&unknown, None).is_err());
+
+    // A Cortex update changes the split
+    fixture.cortex.lp_staking_fee_share_bps = 6_000;
+    fixture.cortex.protocol_fee_share_bps = 2_000;
+    amm.refresh(&fixture.source()).unwrap();
+    assert_eq!(amm.fee_split().unwrap().lp_staking_bps, 6_000);
+    assert_eq!(
+        amm.attribute_fees(&sell_one_sol(), None)
+            .unwrap()
+            .lp_staking_amount,
+        swap_fees.total_amount * 6_000 / 10_000
+    );
+
+    // Shares must add up, and a referrer can't take more than the protocol
+    fixture.cortex.lp_staking_fee_share_bps = 8_000;
+    amm.refresh(&fixture.source()).unwrap();
+    assert!(amm.attribute_fees(&sell_one_sol(), None).is_err());
+
+    fixture.cortex.lp_staking_fee_share_bps = 6_000;
+    fixture.cortex.referrer_fee_share_bps = 2_500;
+    amm.refresh(&fixture.source()).unwrap();
+    assert!(amm
+        .attribute_fees(&sell_one_sol(), Some(&referred_owner))
+        .is_err());
+
+    // Pools can't be loaded without their Cortex
+    let mut source = fixture.source();
+    source.accounts.remove(&pda(&[b"cortex"]));
+    assert!(PoolAmm::load(&source, fixture.pool_key, None).is_err());
+}
+
+/// A profile of `owner` without any discount, so fees only differ by referrer.
+fn profile_account(owner: &Pubkey, referrer: &Pubkey) -> Account {
+    let mut profile: UserProfile = bytemuck::Zeroable::zeroed();
+    profile.owner = *owner;
+    profile.referrer_profile = *referrer;
+
+    program_account(&profile)
+}
+
+fn assert_split(fees: &FeeAttribution, split: &FeeSplit, has_referrer: bool) {
+    let share = |bps: u64| fees.total_amount * bps / 10_000;
+
+    assert_eq!(fees.lp_staking_amount, share(split.lp_staking_bps));
+    assert_eq!(fees.lm_staking_amount, share(split.lm_staking_bps));
+    if has_referrer {
+        assert_eq!(fees.referrer_amount, share(split.referrer_bps));
+    }
+    assert_eq!(
+        fees.lp_staking_amount
+            + fees.lm_staking_amount
+            + fees.protocol_amount
+            + fees.referrer_amount,
+        fees.total_amount
+    );
+}
+
+fn sell_one_sol() -> QuoteParams {
+    QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: WSOL,
+        output_mint: USDC,
+        swap_mode: SwapMode::ExactIn,
+    }
+}
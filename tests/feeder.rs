@@ -0,0 +1,202 @@
+#![cfg(feature = "stream")]
+
+mod common;
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use adrena::state::oracle::OraclePrice;
+use anchor_lang::AnchorDeserialize;
+use common::{custom_oracle_account, program_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{
+    AccountConnection, AccountSubscriber, AccountUpdates, PoolFeeder, QuoteChange, SharedPoolAmm,
+};
+use jupiter_amm_interface::{QuoteParams, SwapMode};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::sync::mpsc;
+
+type UpdateSender = mpsc::UnboundedSender<anyhow::Result<(Pubkey, Account)>>;
+
+/// One connection of the mock subscriber, tracking the keys subscribed to.
+#[derive(Clone, Default)]
+struct MockConnection {
+    keys: Arc<Mutex<HashSet<Pubkey>>>,
+}
+
+impl AccountConnection for MockConnection {
+    fn subscribe(&mut self, key: Pubkey) -> anyhow::Result<()> {
+        assert!(
+            self.keys.lock().unwrap().insert(key),
+            "{key} subscribed twice"
+        );
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, key: Pubkey) -> anyhow::Result<()> {
+        assert!(
+            self.keys.lock().unwrap().remove(&key),
+            "{key} not subscribed"
+        );
+        Ok(())
+    }
+}
+
+/// Stands in for a websocket server, the test pushes account changes to the
+/// latest connection.
+#[derive(Clone, Default)]
+struct MockSubscriber {
+    connections: Arc<Mutex<Vec<(MockConnection, UpdateSender)>>>,
+}
+
+impl AccountSubscriber for MockSubscriber {
+    type Connection = MockConnection;
+
+    fn connect(&self) -> anyhow::Result<(MockConnection, AccountUpdates)> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let connection = MockConnection::default();
+        self.connections
+            .lock()
+            .unwrap()
+            .push((connection.clone(), sender));
+
+        Ok((connection, receiver))
+    }
+}
+
+impl MockSubscriber {
+    /// Keys subscribed to on the latest connection.
+    fn keys(&self) -> HashSet<Pubkey> {
+        let connections = self.connections.lock().unwrap();
+        connections
+            .last()
+            .map(|(connection, _)| connection.keys.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    fn push(&self, key: Pubkey, account: Account) {
+        let connections = self.connections.lock().unwrap();
+        let (connection, sender) = connections.last().unwrap();
+
+        assert!(connection.keys.lock().unwrap().contains(&key));
+        sender.send(Ok((key, account))).unwrap();
+    }
+}
+
+fn sol_price(amm: &SharedPoolAmm, sol_oracle: &Pubkey) -> u64 {
+    let snapshot = amm.current().snapshot().unwrap();
+    let (_, price) = snapshot
+        .oracle_prices
+        .iter()
+        .find(|(key, _)| key == sol_oracle)
+        .unwrap();
+
+    OraclePrice::try_from_slice(price).unwrap().price
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    let wait = async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    };
+
+    tokio::time::timeout(Duration::from_secs(10), wait)
+        .await
+        .expect("Timed out");
+}
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    fixture.custody_mut(&USDC).assets.owned = 1_000_000_000_000;
+    fixture.custody_mut(&WSOL).assets.owned = 10_000_000_000_000;
+
+    let sol_oracle = fixture.custody_mut(&WSOL).oracle.oracle_account;
+    let sol_custody = fixture.pool.custodies[1];
+
+    let feeder = PoolFeeder::new(
+        Box::new(fixture.source()),
+        fixture.pool_key,
+        None,
+        vec![QuoteParams {
+            amount: 1_000_000_000,
+            input_mint: WSOL,
+            output_mint: USDC,
+            swap_mode: SwapMode::ExactIn,
+        }],
+        50,
+    )
+    .unwrap();
+
+    let amm = feeder.amm();
+    let mut changes = feeder.subscribe_changes();
+    let subscriber = MockSubscriber::default();
+
+    let keys: HashSet<Pubkey> = amm.current().subscription_keys().into_iter().collect();
+    assert_eq!(keys.len(), 3 + 3 * fixture.custodies.len());
+    assert!(keys.contains(&fixture.pool_key));
+    assert!(keys.contains(&fixture.lp_token_mint));
+    assert!(keys.contains(&sol_oracle));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let run = tokio::spawn(feeder.run(subscriber.clone()));
+
+        wait_until(|| subscriber.keys() == keys).await;
+
+        // +1% is above the 0.5% threshold
+        subscriber.push(sol_oracle, custom_oracle_account(14_645_000_000, -8));
+        let first: QuoteChange = changes.recv().await.unwrap();
+        assert_eq!(first.input_mint, WSOL);
+        assert!(first.out_amount.unwrap() > first.previous_out_amount.unwrap());
+
+        // +0.1% isn't, +0.6% more is once added up since the last notification
+        subscriber.push(sol_oracle, custom_oracle_account(14_660_000_000, -8));
+        subscriber.push(sol_oracle, custom_oracle_account(14_750_000_000, -8));
+        let second = changes.recv().await.unwrap();
+        assert_eq!(second.previous_out_amount, first.out_amount);
+        assert_eq!(sol_price(&amm, &sol_oracle), 14_750_000_000);
+
+        // An update the pool can't be read with is skipped, the next one applies
+        // on top of the last good state
+        let mut truncated = custom_oracle_account(14_850_000_000, -8);
+        truncated.data.truncate(8);
+        subscriber.push(sol_oracle, truncated);
+        subscriber.push(sol_oracle, custom_oracle_account(14_850_000_000, -8));
+        let third = changes.recv().await.unwrap();
+        assert_eq!(third.previous_out_amount, second.out_amount);
+        assert_eq!(sol_price(&amm, &sol_oracle), 14_850_000_000);
+
+        // Removing the SOL custody only unsubscribes from its accounts, adding
+        // it back subscribes to them again, on the same connection
+        let mut pool = fixture.pool;
+        pool.custodies[1] = Pubkey::default();
+        subscriber.push(fixture.pool_key, program_account(&pool));
+        assert_eq!(changes.recv().await.unwrap().out_amount, None);
+        let removed: HashSet<Pubkey> = [sol_custody, sol_oracle, WSOL].into();
+        assert_eq!(subscriber.keys(), &keys - &removed);
+
+        subscriber.push(fixture.pool_key, program_account(&fixture.pool));
+        assert_eq!(changes.recv().await.unwrap().out_amount, third.out_amount);
+        assert_eq!(subscriber.keys(), keys);
+        assert_eq!(subscriber.connections.lock().unwrap().len(), 1);
+
+        // A lost connection is reopened, with the accounts reloaded from the
+        // source since changes were missed in between
+        subscriber.connections.lock().unwrap().clear();
+        wait_until(|| subscriber.keys() == keys).await;
+        let reloaded = changes.recv().await.unwrap();
+        assert!(reloaded.out_amount < third.out_amount);
+        assert_eq!(sol_price(&amm, &sol_oracle), 14_500_000_000);
+
+        assert!(!run.is_finished());
+        run.abort();
+    });
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{pda, token_2022_mint_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_amm_interface::{Amm, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    let amm = fixture.load().unwrap();
+    let lookup_table = amm.get_lookup_table_addresses();
+    let owner = Pubkey::new_unique();
+    let user_token_accounts = amm.get_user_token_accounts(&owner);
+    let user_profile = pda(&[b"user_profile", owner.as_ref()]);
+
+    for (source_mint, destination_mint) in [
+        (USDC, WSOL),
+        (WSOL, USDC),
+        (USDC, fixture.lp_token_mint),
+        (fixture.lp_token_mint, WSOL),
+    ] {
+        let metas = amm
+            .get_swap_and_account_metas(&SwapParams {
+                in_amount: 1_000_000,
+                out_amount: 0,
+                source_mint,
+                destination_mint,
+                source_token_account: user_token_accounts[&source_mint],
+                destination_token_account: user_token_accounts[&destination_mint],
+                token_transfer_authority: owner,
+                open_order_address: None,
+                quote_mint_to_referrer: None,
+                jupiter_program_id: &Pubkey::default(),
+                missing_dynamic_accounts_as_default: false,
+            })
+            .unwrap()
+            .account_metas;
+
+        for meta in metas {
+            let is_user_specific = meta.pubkey == owner
+                || meta.pubkey == user_profile
+                || user_token_accounts.values().any(|a| *a == meta.pubkey);
+
+            assert!(
+                is_user_specific || lookup_table.contains(&meta.pubkey),
+                "{} is missing from the lookup table",
+                meta.pubkey
+            );
+        }
+    }
+
+    let (_, instructions) = amm.get_lookup_table_instructions(owner, owner, 1);
+    assert_eq!(instructions.len(), 1 + lookup_table.len().div_ceil(30));
+
+    // Token programs of mixed mints come in the same order whatever the order
+    // the mints are held in
+    fixture
+        .accounts
+        .insert(WSOL, token_2022_mint_account(9, None));
+    let lookup_table = fixture.load().unwrap().get_lookup_table_addresses();
+    for _ in 0..10 {
+        assert_eq!(
+            fixture.load().unwrap().get_lookup_table_addresses(),
+            lookup_table
+        );
+    }
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::{mint_account, usdc_wsol_pool, USDC, WSOL};
+use rust_decimal::Decimal;
+
+#[test]
+fn test() {
+    // 1,105,000 USDC and 1000 SOL at $145 backing 1,000,000 ALP
+    let mut fixture = usdc_wsol_pool();
+    fixture.custody_mut(&USDC).assets.owned = 1_105_000_000_000;
+    fixture.custody_mut(&WSOL).assets.owned = 1_000_000_000_000;
+    let amm = fixture.load().unwrap();
+
+    assert_eq!(amm.lp_price_usd().unwrap(), Some(Decimal::new(125, 2)));
+
+    let nav = amm.lp_nav_breakdown().unwrap();
+    assert_eq!(nav.aum_usd, 1_250_000_000_000);
+    assert_eq!(nav.lp_supply, 1_000_000_000_000);
+    assert_eq!(nav.lp_price_usd, Some(Decimal::new(125, 2)));
+    assert_eq!(nav.custodies.len(), 2);
+    assert_eq!(nav.custodies[0].mint, USDC);
+    assert_eq!(nav.custodies[0].owned_usd, 1_105_000_000_000);
+    assert_eq!(nav.custodies[0].current_ratio, 8_840);
+    assert_eq!(nav.custodies[1].mint, WSOL);
+    assert_eq!(nav.custodies[1].owned_usd, 145_000_000_000);
+    assert_eq!(nav.custodies[1].current_ratio, 1_160);
+
+    // Without fees, 125 USDC mint exactly 100 ALP at NAV
+    let mint = amm.lp_mint_price(USDC, 125_000_000).unwrap();
+    assert_eq!(mint.lp_amount, 100_000_000);
+    assert_eq!(mint.token_amount, 125_000_000);
+    assert_eq!(mint.fee_amount, 0);
+    assert_eq!(mint.price_usd, Decimal::new(125, 2));
+    assert_eq!(mint.premium_pct, Decimal::ZERO);
+
+    // Redeeming them into SOL loses the rounding of $125 down to lamports
+    let redeem = amm.lp_redeem_price(WSOL, 100_000_000).unwrap();
+    assert_eq!(redeem.lp_amount, 100_000_000);
+    assert_eq!(redeem.token_amount, 862_068_965);
+    assert_eq!(redeem.fee_amount, 0);
+    assert_eq!(redeem.price_usd, Decimal::new(124_999_999_925, 11));
+    assert_eq!(redeem.premium_pct, Decimal::new(6, 8));
+
+    // No ALP in circulation has no price, rather than a made up one
+    fixture
+        .accounts
+        .insert(fixture.lp_token_mint, mint_account(6, 0));
+    let amm = fixture.load().unwrap();
+    assert_eq!(amm.lp_price_usd().unwrap(), None);
+    assert_eq!(amm.lp_nav_breakdown().unwrap().lp_price_usd, None);
+    assert!(amm.lp_redeem_price(WSOL, 100_000_000).is_err());
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{usdc_wsol_pool, USDC, WSOL};
+use jupiter_amm_interface::{Amm, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    let amm = fixture.load().unwrap();
+    let owner = Pubkey::new_unique();
+    let user_token_accounts = amm.get_user_token_accounts(&owner);
+
+    assert_eq!(user_token_accounts.len(), 3);
+    assert!(user_token_accounts.contains_key(&fixture.lp_token_mint));
+
+    let swap_params = |source_mint: Pubkey, destination_mint: Pubkey| SwapParams {
+        in_amount: 1_000_000,
+        out_amount: 0,
+        source_mint,
+        destination_mint,
+        source_token_account: user_token_accounts[&source_mint],
+        destination_token_account: user_token_accounts[&destination_mint],
+        token_transfer_authority: owner,
+        open_order_address: None,
+        quote_mint_to_referrer: None,
+        jupiter_program_id: &Pubkey::default(),
+        missing_dynamic_accounts_as_default: false,
+    };
+
+    // Every flow points at the custody vaults, never at the user's accounts
+    for (source_mint, destination_mint) in [
+        (USDC, WSOL),
+        (USDC, fixture.lp_token_mint),
+        (fixture.lp_token_mint, WSOL),
+    ] {
+        let metas = amm
+            .get_swap_and_account_metas(&swap_params(source_mint, destination_mint))
+            .unwrap()
+            .account_metas;
+
+        for (custody_key, custody) in &fixture.custodies {
+            if metas.iter().any(|m| m.pubkey == *custody_key) {
+                assert!(metas.iter().any(|m| m.pubkey == custody.token_account));
+            }
+        }
+    }
+
+    // A custody pointing to something else than its vault PDA is flagged
+    fixture.custody_mut(&WSOL).token_account = Pubkey::new_unique();
+
+    let amm = fixture.load().unwrap();
+
+    assert!(amm
+        .get_swap_and_account_metas(&swap_params(USDC, WSOL))
+        .is_err());
+    assert!(amm
+        .get_swap_and_account_metas(&swap_params(fixture.lp_token_mint, WSOL))
+        .is_err());
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{usdc_wsol_pool, SPL_TOKEN_ID, USDC, WSOL};
+use jupiter_adrena::NATIVE_MINT;
+use jupiter_amm_interface::SwapParams;
+use solana_sdk::{pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::instruction::{close_account, sync_native};
+
+#[test]
+fn test() {
+    assert_eq!(NATIVE_MINT, WSOL);
+
+    let fixture = usdc_wsol_pool();
+    let amm = fixture.load().unwrap();
+    let owner = Pubkey::new_unique();
+    let wsol_account = get_associated_token_address(&owner, &WSOL);
+    let usdc_account = get_associated_token_address(&owner, &USDC);
+
+    let swap_params = |source_mint: Pubkey,
+                       destination_mint: Pubkey,
+                       source_token_account: Pubkey,
+                       destination_token_account: Pubkey| SwapParams {
+        in_amount: 1_000_000_000,
+        out_amount: 0,
+        source_mint,
+        destination_mint,
+        source_token_account,
+        destination_token_account,
+        token_transfer_authority: owner,
+        open_order_address: None,
+        quote_mint_to_referrer: None,
+        jupiter_program_id: &Pubkey::default(),
+        missing_dynamic_accounts_as_default: false,
+    };
+
+    let create = create_associated_token_account_idempotent(&owner, &owner, &WSOL, &SPL_TOKEN_ID);
+    let close = close_account(&SPL_TOKEN_ID, &wsol_account, &owner, &owner, &[]).unwrap();
+
+    // From SOL: wrap exactly the input amount, then unwrap what's left
+    let from_sol = swap_params(WSOL, USDC, wsol_account, usdc_account);
+    let swap = amm.get_native_sol_swap(&from_sol, true).unwrap();
+    assert_eq!(
+        swap.setup_instructions,
+        vec![
+            create.clone(),
+            system_instruction::transfer(&owner, &wsol_account, 1_000_000_000),
+            sync_native(&SPL_TOKEN_ID, &wsol_account).unwrap(),
+        ]
+    );
+    assert_eq!(swap.cleanup_instructions, vec![close.clone()]);
+
+    // Keeping the account leaves the setup as is
+    let swap = amm.get_native_sol_swap(&from_sol, false).unwrap();
+    assert_eq!(swap.setup_instructions.len(), 3);
+    assert!(swap.cleanup_instructions.is_empty());
+
+    // To SOL: only make sure the account exists to receive the output
+    let to_sol = swap_params(USDC, WSOL, usdc_account, wsol_account);
+    let swap = amm.get_native_sol_swap(&to_sol, true).unwrap();
+    assert_eq!(swap.setup_instructions, vec![create]);
+    assert_eq!(swap.cleanup_instructions, vec![close]);
+    assert!(amm
+        .get_native_sol_swap(&to_sol, false)
+        .unwrap()
+        .cleanup_instructions
+        .is_empty());
+
+    // Without SOL on either side there is nothing to wrap or close
+    let lp = swap_params(
+        USDC,
+        fixture.lp_token_mint,
+        usdc_account,
+        Pubkey::new_unique(),
+    );
+    let swap = amm.get_native_sol_swap(&lp, true).unwrap();
+    assert!(swap.setup_instructions.is_empty());
+    assert!(swap.cleanup_instructions.is_empty());
+
+    // Any other account than the wSOL ATA would be funded or closed by mistake
+    assert!(amm
+        .get_native_sol_swap(
+            &swap_params(WSOL, USDC, Pubkey::new_unique(), usdc_account),
+            true
+        )
+        .is_err());
+    assert!(amm
+        .get_native_sol_swap(
+            &swap_params(USDC, WSOL, usdc_account, Pubkey::new_unique()),
+            true
+        )
+        .is_err());
+}
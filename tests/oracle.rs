@@ -0,0 +1,99 @@
+mod common;
+
+use adrena::state::oracle::OracleType;
+use common::{custom_oracle_account, pyth_price_update_v2_account, PROGRAM_ID};
+use jupiter_adrena::oracle::{
+    decode_oracle_type, parse_oracle_account, PYTH_PUSH_ORACLE_ID, PYTH_RECEIVER_ID,
+};
+use jupiter_adrena::{AccountSource, AccountValidationError, FixtureAccountSource};
+use solana_sdk::{account::Account, pubkey, pubkey::Pubkey};
+
+const ORACLE_KEY: Pubkey = pubkey!("5SSkXsEKQepHHAewytPVwdej4epN1nxgLVM84L4KXgy7");
+
+#[test]
+fn test() {
+    assert!(matches!(
+        decode_oracle_type(OracleType::Pyth as u8),
+        Ok(OracleType::Pyth)
+    ));
+    assert!(decode_oracle_type(u8::MAX).is_err());
+
+    // Pyth PriceUpdateV2
+    let price = parse_oracle_account(
+        OracleType::Pyth,
+        &ORACLE_KEY,
+        &pyth_price_update_v2_account(14_512_345_678, -8),
+        &PROGRAM_ID,
+    )
+    .unwrap();
+    assert_eq!((price.price, price.exponent), (14_512_345_678, -8));
+
+    // Sponsored feeds are PriceUpdateV2 accounts owned by the push oracle
+    let mut sponsored = pyth_price_update_v2_account(14_512_345_678, -8);
+    sponsored.owner = PYTH_PUSH_ORACLE_ID;
+    let price =
+        parse_oracle_account(OracleType::Pyth, &ORACLE_KEY, &sponsored, &PROGRAM_ID).unwrap();
+    assert_eq!((price.price, price.exponent), (14_512_345_678, -8));
+
+    // Legacy Pyth price accounts dumped from devnet
+    let dumps = FixtureAccountSource::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/oracles/pyth_legacy"
+    ));
+    for (key, expected) in [
+        (ORACLE_KEY, 99_999_556),
+        (
+            pubkey!("38xoQ4oeJCBrcVvca2cGk7iV1dAfrmTR1kmhSCJQ8Jto"),
+            99_982_237,
+        ),
+        (
+            pubkey!("EdVCmQ9FSPcVe5YySXDPCRmc8aDQLKJ9xvYBMZPie1Vw"),
+            373_845_083_334,
+        ),
+        (
+            pubkey!("HovQMDrbAgAYPCmHVSrezcSmkMtXSSUsLDFANExrZh2J"),
+            7_012_196_875_000,
+        ),
+        (
+            pubkey!("J83w4HKfqxwcq3BEMMkPFSppX3gqekLyLJBexebFVkix"),
+            18_088_213_075,
+        ),
+    ] {
+        let account = dumps.get_accounts(&[key]).unwrap().remove(&key).unwrap();
+        let price = parse_oracle_account(OracleType::Pyth, &key, &account, &PROGRAM_ID).unwrap();
+        assert_eq!((price.price, price.exponent), (expected, -8));
+    }
+
+    // A legacy account is only trusted from the legacy Pyth programs
+    let mut spoofed = dumps.get_accounts(&[ORACLE_KEY]).unwrap()[&ORACLE_KEY].clone();
+    spoofed.owner = Pubkey::new_unique();
+    assert!(parse_oracle_account(OracleType::Pyth, &ORACLE_KEY, &spoofed, &PROGRAM_ID).is_err());
+
+    // Adrena custom oracle
+    let price = parse_oracle_account(
+        OracleType::Custom,
+        &ORACLE_KEY,
+        &custom_oracle_account(3_738_450, -4),
+        &PROGRAM_ID,
+    )
+    .unwrap();
+    assert_eq!((price.price, price.exponent), (3_738_450, -4));
+
+    // A custom oracle account owned by someone else is rejected
+    let mut spoofed = custom_oracle_account(3_738_450, -4);
+    spoofed.owner = Pubkey::new_unique();
+    let err =
+        parse_oracle_account(OracleType::Custom, &ORACLE_KEY, &spoofed, &PROGRAM_ID).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<AccountValidationError>(),
+        Some(AccountValidationError::InvalidOracleOwner { .. })
+    ));
+
+    assert!(parse_oracle_account(
+        OracleType::None,
+        &ORACLE_KEY,
+        &custom_oracle_account(3_738_450, -4),
+        &PROGRAM_ID
+    )
+    .is_err());
+}
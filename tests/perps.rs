@@ -0,0 +1,109 @@
+mod common;
+
+use common::{clock_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{OpenPositionParams, PoolAmm, Side};
+use rust_decimal::Decimal;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.pricing.trade_spread_long = 10;
+        custody.pricing.trade_spread_short = 10;
+        custody.pricing.max_initial_leverage = 500_000;
+        custody.pricing.max_leverage = 1_000_000;
+        custody.fees.open_position = 10;
+        custody.fees.close_position = 16;
+        // 0.1% per hour, one hour since the last update
+        custody.borrow_rate_state.current_rate = 1_000_000;
+    }
+
+    let mut source = fixture.source();
+    let (clock_key, clock) = clock_account(3_600);
+    source.accounts.insert(clock_key, clock);
+    let amm = PoolAmm::load(&source, fixture.pool_key, None).unwrap();
+
+    // 1000 USD long at 5x, collateral in SOL
+    let long = amm
+        .quote_open_position(&OpenPositionParams {
+            side: Side::Long,
+            mint: WSOL,
+            collateral_mint: WSOL,
+            size_usd: Decimal::from(1_000),
+            leverage: 50_000,
+        })
+        .unwrap();
+
+    assert_eq!(long.entry_price, Decimal::new(145_145, 3));
+    assert_eq!(long.open_fee_usd, Decimal::ONE);
+    assert_eq!(long.collateral_usd, Decimal::from(200));
+    // 201 USD at 145 USD per SOL, rounded up
+    assert_eq!(long.required_collateral_amount, 1_386_206_897);
+    // 10 USD maintenance margin at 100x and 1.6 USD exit fee left from 200 USD
+    assert_eq!(long.liquidation_price, Decimal::new(117_799_682, 6));
+    assert_eq!(long.position.cumulative_interest_snapshot, 1_000_000);
+
+    // Closing at the same oracle price costs both spreads and the exit fee
+    let close = amm.quote_close_position(&long.position, None).unwrap();
+    assert_eq!(close.exit_price, Decimal::new(144_855, 3));
+    assert!(close.pnl_usd < Decimal::ZERO);
+    assert_eq!(close.exit_fee_usd, Decimal::new(16, 1));
+    assert_eq!(close.borrow_fee_usd, Decimal::ZERO);
+    assert_eq!(
+        close.settled_usd,
+        Decimal::from(200) + close.pnl_usd - Decimal::new(16, 1)
+    );
+    assert!(close.remaining.is_none());
+
+    // Half of a position opened an hour earlier pays half of an hour of borrow
+    let mut older = long.position.clone();
+    older.cumulative_interest_snapshot = 0;
+    let partial = amm
+        .quote_close_position(&older, Some(Decimal::from(500)))
+        .unwrap();
+    assert_eq!(partial.exit_fee_usd, Decimal::new(8, 1));
+    assert_eq!(partial.borrow_fee_usd, Decimal::new(5, 1));
+    assert!((partial.pnl_usd * Decimal::TWO - close.pnl_usd).abs() < Decimal::new(1, 20));
+    let remaining = partial.remaining.unwrap();
+    assert_eq!(remaining.size_usd, Decimal::from(500));
+    assert_eq!(remaining.collateral_usd, Decimal::from(100));
+
+    // Shorts sell below the oracle, collateral in USDC
+    let short = amm
+        .quote_open_position(&OpenPositionParams {
+            side: Side::Short,
+            mint: WSOL,
+            collateral_mint: USDC,
+            size_usd: Decimal::from(1_000),
+            leverage: 50_000,
+        })
+        .unwrap();
+    assert_eq!(short.entry_price, Decimal::new(144_855, 3));
+    assert_eq!(short.required_collateral_amount, 201_000_000);
+    assert_eq!(short.liquidation_price, Decimal::new(172_145_682, 6));
+
+    // Above the max initial leverage
+    assert!(amm
+        .quote_open_position(&OpenPositionParams {
+            side: Side::Long,
+            mint: WSOL,
+            collateral_mint: WSOL,
+            size_usd: Decimal::from(1_000),
+            leverage: 600_000,
+        })
+        .is_err());
+
+    // More than the pool can pay out
+    assert!(amm
+        .quote_open_position(&OpenPositionParams {
+            side: Side::Long,
+            mint: WSOL,
+            collateral_mint: WSOL,
+            size_usd: Decimal::from(1_000_000),
+            leverage: 50_000,
+        })
+        .is_err());
+}
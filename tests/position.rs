@@ -0,0 +1,93 @@
+mod common;
+
+use adrena::state::position::{Position, Side as PositionSide};
+use common::{clock_account, program_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{AccountValidationError, PoolAmm, Side};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.pricing.trade_spread_long = 10;
+        custody.pricing.trade_spread_short = 10;
+        custody.pricing.max_leverage = 1_000_000;
+        custody.fees.close_position = 16;
+        // 0.1% per hour, one hour since the last update
+        custody.borrow_rate_state.current_rate = 1_000_000;
+    }
+
+    let mut source = fixture.source();
+    let (clock_key, clock) = clock_account(3_600);
+    source.accounts.insert(clock_key, clock);
+    let amm = PoolAmm::load(&source, fixture.pool_key, None).unwrap();
+
+    let sol_custody = fixture.custodies[1].0;
+    let long = |pool: Pubkey, entry_price: u64| {
+        let mut position: Position = bytemuck::Zeroable::zeroed();
+        position.owner = Pubkey::new_unique();
+        position.pool = pool;
+        position.custody = sol_custody;
+        position.collateral_custody = sol_custody;
+        position.side = PositionSide::Long as u8;
+        position.price = entry_price * 10_000_000_000;
+        position.size_usd = 1_000_000_000;
+        position.collateral_usd = 200_000_000;
+
+        (Pubkey::new_unique(), program_account(&position))
+    };
+
+    let positions = [
+        long(fixture.pool_key, 145),
+        long(fixture.pool_key, 180),
+        long(Pubkey::new_unique(), 145),
+    ];
+
+    let health = amm.position_health_batch(&positions);
+    assert_eq!(health.len(), 3);
+
+    // 1000 USD long at 145, marked at 145 minus the spread
+    let healthy = health[0].as_ref().unwrap();
+    assert_eq!(healthy.position, positions[0].0);
+    assert_eq!(healthy.side, Side::Long);
+    assert_eq!(healthy.mark_price, Decimal::new(144_855, 3));
+    assert_eq!(healthy.unrealized_pnl_usd, -Decimal::ONE);
+    assert_eq!(healthy.exit_fee_usd, Decimal::new(16, 1));
+    assert_eq!(healthy.borrow_fee_usd, Decimal::ONE);
+    assert_eq!(healthy.margin_usd, Decimal::new(1964, 1));
+    assert_eq!(
+        healthy.leverage,
+        Some(Decimal::from(1_000) / Decimal::new(1964, 1))
+    );
+    // 10 USD maintenance margin plus 2.6 USD of fees left from 200 USD
+    assert_eq!(healthy.liquidation_price, Decimal::new(117_827, 3));
+    assert!(healthy.liquidation_distance_pct > Decimal::from(18));
+    assert!(!healthy.is_liquidatable);
+
+    // Entered at 180, 2.15 USD of margin left is below the 10 USD maintenance
+    let underwater = health[1].as_ref().unwrap();
+    assert_eq!(underwater.margin_usd, Decimal::new(215, 2));
+    assert_eq!(underwater.liquidation_price, Decimal::new(146_268, 3));
+    assert!(underwater.liquidation_distance_pct < Decimal::ZERO);
+    assert!(underwater.is_liquidatable);
+
+    // Positions of other pools are refused without failing the batch
+    assert!(matches!(
+        health[2]
+            .as_ref()
+            .unwrap_err()
+            .downcast_ref::<AccountValidationError>(),
+        Some(AccountValidationError::PositionPoolMismatch { .. })
+    ));
+
+    let decoded = amm
+        .decode_position(&positions[0].0, &positions[0].1)
+        .unwrap();
+    assert_eq!(decoded.entry_price, Decimal::from(145));
+    assert_eq!(decoded.size_usd, Decimal::from(1_000));
+    assert_eq!(decoded.collateral_usd, Decimal::from(200));
+}
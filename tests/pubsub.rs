@@ -0,0 +1,210 @@
+#![cfg(feature = "stream")]
+
+mod common;
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use adrena::state::oracle::OraclePrice;
+use anchor_lang::AnchorDeserialize;
+use common::{custom_oracle_account, usdc_wsol_pool, USDC, WSOL};
+use futures::{SinkExt, StreamExt};
+use jupiter_adrena::{PoolFeeder, PubsubAccountSubscriber, SharedPoolAmm};
+use jupiter_amm_interface::{QuoteParams, SwapMode};
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+/// What the mock websocket server has seen.
+#[derive(Default)]
+struct ServerState {
+    connections: usize,
+    /// Every `accountSubscribe` received, across connections.
+    subscriptions: Vec<Pubkey>,
+    /// Subscription ids on the latest connection.
+    ids: HashMap<Pubkey, u64>,
+    /// Sends to the latest connection.
+    sender: Option<mpsc::UnboundedSender<Message>>,
+}
+
+/// Answers `accountSubscribe` and `accountUnsubscribe` like a validator would.
+async fn serve(listener: TcpListener, state: Arc<Mutex<ServerState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            let (mut write, mut read) = accept_async(stream).await.unwrap().split();
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            {
+                let mut state = state.lock().unwrap();
+                state.connections += 1;
+                state.ids.clear();
+                state.sender = Some(sender.clone());
+            }
+
+            tokio::spawn(async move {
+                while let Some(message) = receiver.recv().await {
+                    if write.send(message).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            while let Some(Ok(message)) = read.next().await {
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let request: Value = serde_json::from_str(&text).unwrap();
+
+                let result = match request["method"].as_str().unwrap() {
+                    "accountSubscribe" => {
+                        let key = Pubkey::from_str(request["params"][0].as_str().unwrap()).unwrap();
+                        let mut state = state.lock().unwrap();
+                        state.subscriptions.push(key);
+                        let id = state.subscriptions.len() as u64;
+                        state.ids.insert(key, id);
+                        json!(id)
+                    }
+                    _ => json!(true),
+                };
+
+                let response = json!({ "jsonrpc": "2.0", "result": result, "id": request["id"] });
+                if sender.send(Message::Text(response.to_string())).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Notifies the latest subscription to `key` of a new `account`.
+fn push(state: &Mutex<ServerState>, key: Pubkey, account: Account) {
+    let state = state.lock().unwrap();
+    let account = UiAccount::encode(&key, &account, UiAccountEncoding::Base64, None, None);
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "accountNotification",
+        "params": {
+            "result": { "context": { "slot": 1 }, "value": account },
+            "subscription": state.ids[&key],
+        },
+    });
+
+    state
+        .sender
+        .as_ref()
+        .unwrap()
+        .send(Message::Text(notification.to_string()))
+        .unwrap();
+}
+
+fn sol_price(amm: &SharedPoolAmm, sol_oracle: &Pubkey) -> u64 {
+    let snapshot = amm.current().snapshot().unwrap();
+    let (_, price) = snapshot
+        .oracle_prices
+        .iter()
+        .find(|(key, _)| key == sol_oracle)
+        .unwrap();
+
+    OraclePrice::try_from_slice(price).unwrap().price
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    let wait = async {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    };
+
+    within_seconds(wait).await;
+}
+
+async fn within_seconds<F: Future>(future: F) -> F::Output {
+    tokio::time::timeout(Duration::from_secs(10), future)
+        .await
+        .expect("Timed out")
+}
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    fixture.custody_mut(&USDC).assets.owned = 1_000_000_000_000;
+    fixture.custody_mut(&WSOL).assets.owned = 10_000_000_000_000;
+
+    let sol_oracle = fixture.custody_mut(&WSOL).oracle.oracle_account;
+
+    let feeder = PoolFeeder::new(
+        Box::new(fixture.source()),
+        fixture.pool_key,
+        None,
+        vec![QuoteParams {
+            amount: 1_000_000_000,
+            input_mint: WSOL,
+            output_mint: USDC,
+            swap_mode: SwapMode::ExactIn,
+        }],
+        50,
+    )
+    .unwrap();
+
+    let amm = feeder.amm();
+    let mut keys = amm.current().subscription_keys();
+    keys.sort();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(ServerState::default()));
+        tokio::spawn(serve(listener, state.clone()));
+
+        let run = tokio::spawn(feeder.run(PubsubAccountSubscriber::new(url)));
+
+        wait_until(|| state.lock().unwrap().ids.len() == keys.len()).await;
+
+        // Every update refreshes the custodies, none of them changes what the
+        // feeder is subscribed to
+        for price in [
+            14_645_000_000,
+            14_660_000_000,
+            14_750_000_000,
+            14_500_000_000,
+        ] {
+            push(&state, sol_oracle, custom_oracle_account(price, -8));
+            wait_until(|| sol_price(&amm, &sol_oracle) == price).await;
+        }
+
+        // An update that can't be applied is skipped without dropping the
+        // connection, the next one applies
+        let mut truncated = custom_oracle_account(14_400_000_000, -8);
+        truncated.data.truncate(8);
+        push(&state, sol_oracle, truncated);
+        push(
+            &state,
+            sol_oracle,
+            custom_oracle_account(14_400_000_000, -8),
+        );
+        wait_until(|| sol_price(&amm, &sol_oracle) == 14_400_000_000).await;
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.connections, 1);
+
+        let mut subscriptions = state.subscriptions.clone();
+        subscriptions.sort();
+        assert_eq!(subscriptions, keys);
+
+        assert!(!run.is_finished());
+        run.abort();
+    });
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::PoolAmm;
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode, SwapParams};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    for mint in [USDC, WSOL] {
+        fixture.custody_mut(&mint).assets.owned = 1_000_000_000_000;
+    }
+    let source = fixture.source();
+    let lp = fixture.lp_token_mint;
+
+    let load = |params: Value| PoolAmm::load(&source, fixture.pool_key, Some(params));
+    let owner = Pubkey::new_unique();
+
+    let can_quote = |amm: &PoolAmm, input_mint: Pubkey, output_mint: Pubkey| {
+        amm.quote(&QuoteParams {
+            amount: 1_000_000,
+            input_mint,
+            output_mint,
+            swap_mode: SwapMode::ExactIn,
+        })
+        .is_ok()
+    };
+    let can_build = |amm: &PoolAmm, source_mint: Pubkey, destination_mint: Pubkey| {
+        let user_token_accounts = amm.get_user_token_accounts(&owner);
+
+        amm.get_swap_and_account_metas(&SwapParams {
+            in_amount: 1_000_000,
+            out_amount: 0,
+            source_mint,
+            destination_mint,
+            source_token_account: user_token_accounts[&source_mint],
+            destination_token_account: user_token_accounts[&destination_mint],
+            token_transfer_authority: owner,
+            open_order_address: None,
+            quote_mint_to_referrer: None,
+            jupiter_program_id: &Pubkey::default(),
+            missing_dynamic_accounts_as_default: false,
+        })
+        .is_ok()
+    };
+    // Whether each route is quoted and built, in the order of `routes`
+    let routes = [
+        (USDC, WSOL),
+        (WSOL, USDC),
+        (USDC, lp),
+        (lp, USDC),
+        (WSOL, lp),
+        (lp, WSOL),
+    ];
+    let allowed = |amm: &PoolAmm| -> Vec<bool> {
+        routes
+            .iter()
+            .map(|(input_mint, output_mint)| {
+                let quoted = can_quote(amm, *input_mint, *output_mint);
+                assert_eq!(quoted, can_build(amm, *input_mint, *output_mint));
+                quoted
+            })
+            .collect()
+    };
+    let reserve_mints = |amm: &PoolAmm| {
+        let mut mints = amm.get_reserve_mints();
+        mints.sort();
+        mints
+    };
+    let mut all_mints = vec![USDC, WSOL, lp];
+    all_mints.sort();
+
+    // Params that are not an object enable everything
+    for params in [Value::Null, json!("route"), json!([]), json!({})] {
+        let amm = load(params).unwrap();
+        assert_eq!(allowed(&amm), vec![true; 6]);
+        assert_eq!(reserve_mints(&amm), all_mints);
+    }
+
+    // An object that doesn't parse is not silently ignored
+    assert!(load(json!({ "allowLpMint": "no" })).is_err());
+
+    // Blocking one direction keeps the other one
+    let amm = load(json!({ "blockedPairs": [[USDC.to_string(), WSOL.to_string()]] })).unwrap();
+    assert_eq!(allowed(&amm), vec![false, true, true, true, true, true]);
+    assert_eq!(reserve_mints(&amm), all_mints);
+
+    // Only listed routes, ALP ones included, are allowed by a whitelist
+    let amm = load(json!({ "allowedPairs": [[USDC.to_string(), WSOL.to_string()]] })).unwrap();
+    assert_eq!(allowed(&amm), vec![true, false, false, false, false, false]);
+    let mut mints = vec![USDC, WSOL];
+    mints.sort();
+    assert_eq!(reserve_mints(&amm), mints);
+
+    let amm = load(json!({ "allowLpMint": false })).unwrap();
+    assert_eq!(allowed(&amm), vec![true, true, false, true, false, true]);
+    assert_eq!(reserve_mints(&amm), all_mints);
+
+    let amm = load(json!({ "allowLpRedeem": false })).unwrap();
+    assert_eq!(allowed(&amm), vec![true, true, true, false, true, false]);
+    assert_eq!(reserve_mints(&amm), all_mints);
+
+    // ALP disappears from the reserve mints once it can't be traded either way
+    let amm = load(json!({ "allowLpMint": false, "allowLpRedeem": false })).unwrap();
+    assert_eq!(allowed(&amm), vec![true, true, false, false, false, false]);
+    assert_eq!(reserve_mints(&amm), mints);
+
+    // A blacklist applies on top of a whitelist
+    let amm = load(json!({
+        "allowedPairs": [[USDC.to_string(), WSOL.to_string()], [WSOL.to_string(), USDC.to_string()]],
+        "blockedPairs": [[WSOL.to_string(), USDC.to_string()]],
+    }))
+    .unwrap();
+    assert_eq!(allowed(&amm), vec![true, false, false, false, false, false]);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::PriceOverride;
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use rust_decimal::Decimal;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    fixture.custody_mut(&USDC).assets.owned = 1_000_000_000_000;
+    fixture.custody_mut(&WSOL).assets.owned = 10_000_000_000_000;
+    let amm = fixture.load().unwrap();
+
+    let shock = |pct: i64| {
+        amm.with_price_overrides(&HashMap::from([(
+            WSOL,
+            PriceOverride::ShockPct(Decimal::from(pct)),
+        )]))
+    };
+
+    let sell_sol = || QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: WSOL,
+        output_mint: USDC,
+        swap_mode: SwapMode::ExactIn,
+    };
+    let buy_sol = || QuoteParams {
+        amount: 100_000_000,
+        input_mint: USDC,
+        output_mint: WSOL,
+        swap_mode: SwapMode::ExactIn,
+    };
+
+    // $1M of USDC and 10k SOL at $145
+    let aum_usd = amm.lp_nav_breakdown().unwrap().aum_usd;
+    assert_eq!(aum_usd, 2_450_000_000_000);
+
+    // SOL 10% down: selling it pays less, buying it gets more, the pool is worth less
+    let crash = shock(-10).unwrap();
+    assert_eq!(
+        crash.lp_nav_breakdown().unwrap().aum_usd,
+        1_000_000_000_000 + 1_305_000_000_000
+    );
+
+    let comparisons = amm
+        .compare_quotes(&crash, &[sell_sol(), buy_sol()])
+        .unwrap();
+    assert!(comparisons[0].scenario_out_amount < comparisons[0].base_out_amount);
+    assert!(comparisons[0].out_amount_change_pct < Decimal::ZERO);
+    assert!(comparisons[1].scenario_out_amount > comparisons[1].base_out_amount);
+    assert!(comparisons[1].out_amount_change_pct > Decimal::ZERO);
+
+    // SOL 10% up moves everything the other way
+    let rally = shock(10).unwrap();
+    assert_eq!(
+        rally.lp_nav_breakdown().unwrap().aum_usd,
+        1_000_000_000_000 + 1_595_000_000_000
+    );
+    assert!(
+        rally.quote(&sell_sol()).unwrap().out_amount > amm.quote(&sell_sol()).unwrap().out_amount
+    );
+    assert!(
+        rally.quote(&buy_sol()).unwrap().out_amount < amm.quote(&buy_sol()).unwrap().out_amount
+    );
+
+    // The base quoter is left untouched
+    assert_eq!(amm.lp_nav_breakdown().unwrap().aum_usd, aum_usd);
+
+    // A price can't be shocked to zero or below
+    assert!(shock(-100).is_err());
+    assert!(shock(-150).is_err());
+    assert!(shock(-99).is_ok());
+}
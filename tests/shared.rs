@@ -0,0 +1,84 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use common::{custom_oracle_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::SharedPoolAmm;
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    fixture.custody_mut(&USDC).assets.owned = 1_000_000_000_000;
+    fixture.custody_mut(&WSOL).assets.owned = 10_000_000_000_000;
+
+    let sol_oracle = fixture.custody_mut(&WSOL).oracle.oracle_account;
+
+    let low = fixture.source();
+    let mut high = fixture.source();
+    high.accounts
+        .insert(sol_oracle, custom_oracle_account(16_000_000_000, -8));
+
+    let params = sell_one_sol();
+
+    let amm = fixture.load().unwrap();
+    let low_out = amm.quote(&params).unwrap().out_amount;
+
+    let shared = SharedPoolAmm::new(amm);
+    let before = shared.current();
+
+    shared.refresh(&high).unwrap();
+    let high_out = shared.current().quote(&params).unwrap().out_amount;
+    assert!(high_out > low_out);
+
+    // Published states are immutable, earlier readers keep their prices
+    assert_eq!(before.quote(&params).unwrap().out_amount, low_out);
+
+    // Readers always see a fully applied update while a writer keeps flipping
+    let done = Arc::new(AtomicBool::new(false));
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            let done = done.clone();
+
+            thread::spawn(move || {
+                let params = sell_one_sol();
+                let mut quotes = 0;
+
+                while !done.load(Ordering::Relaxed) {
+                    let out_amount = shared.current().quote(&params).unwrap().out_amount;
+                    assert!(out_amount == low_out || out_amount == high_out);
+                    quotes += 1;
+                }
+
+                quotes
+            })
+        })
+        .collect();
+
+    for i in 0..200 {
+        shared
+            .refresh(if i % 2 == 0 { &low } else { &high })
+            .unwrap();
+    }
+    done.store(true, Ordering::Relaxed);
+
+    for reader in readers {
+        assert!(reader.join().unwrap() > 0);
+    }
+}
+
+fn sell_one_sol() -> QuoteParams {
+    QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: WSOL,
+        output_mint: USDC,
+        swap_mode: SwapMode::ExactIn,
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use adrena::state::user_profile::UserProfile;
+use common::{program_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{PoolAmm, PoolAmmSnapshot};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = 30;
+        custody.fees.swap_out = 30;
+        custody.fees.add_liquidity = 10;
+        custody.fees.remove_liquidity = 10;
+    }
+
+    let mut amm = fixture.load().unwrap();
+
+    // One owner with a 25% discount, one known to have no profile
+    let owner = Pubkey::new_unique();
+    let mut profile: UserProfile = bytemuck::Zeroable::zeroed();
+    profile.owner = owner;
+    profile.fee_discount_bps = 2_500;
+    amm.load_user_profile(owner, Some(&program_account(&profile)))
+        .unwrap();
+    let without_profile = Pubkey::new_unique();
+    amm.load_user_profile(without_profile, None).unwrap();
+
+    let snapshot = amm.snapshot().unwrap();
+
+    let from_json =
+        PoolAmm::from_snapshot(&PoolAmmSnapshot::from_json(&snapshot.to_json().unwrap()).unwrap())
+            .unwrap();
+    let from_bytes = PoolAmm::from_snapshot(
+        &PoolAmmSnapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap(),
+    )
+    .unwrap();
+
+    for restored in [from_json, from_bytes] {
+        assert_eq!(
+            restored.snapshot().unwrap().to_bytes().unwrap(),
+            snapshot.to_bytes().unwrap()
+        );
+        assert_eq!(
+            restored.get_accounts_to_update(),
+            amm.get_accounts_to_update()
+        );
+        assert_eq!(restored.user_profile(&owner), amm.user_profile(&owner));
+        assert_eq!(restored.user_profile(&without_profile), None);
+
+        for (input_mint, output_mint) in [
+            (USDC, WSOL),
+            (WSOL, USDC),
+            (WSOL, fixture.lp_token_mint),
+            (fixture.lp_token_mint, USDC),
+        ] {
+            let params = QuoteParams {
+                amount: 1_000_000_000,
+                input_mint,
+                output_mint,
+                swap_mode: SwapMode::ExactIn,
+            };
+
+            let expected = amm.quote(&params).unwrap();
+            let quote = restored.quote(&params).unwrap();
+            assert!(expected.out_amount > 0);
+            assert!(expected.fee_amount > 0);
+            assert_eq!(quote.in_amount, expected.in_amount);
+            assert_eq!(quote.out_amount, expected.out_amount);
+            assert_eq!(quote.fee_amount, expected.fee_amount);
+            assert_eq!(quote.fee_mint, expected.fee_mint);
+            assert_eq!(quote.fee_pct, expected.fee_pct);
+
+            let expected = amm.quote_for_user(&params, &owner).unwrap();
+            let quote = restored.quote_for_user(&params, &owner).unwrap();
+            assert_eq!(quote.out_amount, expected.out_amount);
+            assert_eq!(quote.fee_amount, expected.fee_amount);
+        }
+    }
+
+    let mut unsupported = snapshot;
+    unsupported.version += 1;
+    assert!(PoolAmmSnapshot::from_bytes(&unsupported.to_bytes().unwrap()).is_err());
+    assert!(PoolAmmSnapshot::from_json(&unsupported.to_json().unwrap()).is_err());
+    assert!(PoolAmm::from_snapshot(&unsupported).is_err());
+}
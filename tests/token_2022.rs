@@ -0,0 +1,101 @@
+mod common;
+
+use common::{
+    token_2022_mint_account, CustodyFixture, PoolFixture, SPL_TOKEN_ID, TOKEN_2022_ID, USDC,
+};
+use jupiter_adrena::PoolAmm;
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+const FEE_TOKEN: Pubkey = Pubkey::new_from_array([1; 32]);
+const PLAIN_TOKEN: Pubkey = Pubkey::new_from_array([2; 32]);
+
+/// USDC, a Token-2022 mint withholding 1% of transfers and one without fees,
+/// all at $1.
+fn pool(usdc_program: Pubkey, transfer_fee_bps: Option<u16>) -> PoolFixture {
+    let custody = |mint| CustodyFixture {
+        mint,
+        decimals: 6,
+        price: 100_000_000,
+        exponent: -8,
+    };
+    let mut fixture = PoolFixture::new(&[custody(USDC), custody(FEE_TOKEN), custody(PLAIN_TOKEN)]);
+
+    for mint in [USDC, FEE_TOKEN, PLAIN_TOKEN] {
+        fixture.custody_mut(&mint).assets.owned = 1_000_000_000_000;
+    }
+
+    if usdc_program == TOKEN_2022_ID {
+        fixture
+            .accounts
+            .insert(USDC, token_2022_mint_account(6, None));
+    }
+    fixture
+        .accounts
+        .insert(FEE_TOKEN, token_2022_mint_account(6, transfer_fee_bps));
+    fixture
+        .accounts
+        .insert(PLAIN_TOKEN, token_2022_mint_account(6, None));
+
+    fixture
+}
+
+fn out_amount(amm: &PoolAmm, input_mint: Pubkey, output_mint: Pubkey, amount: u64) -> u64 {
+    amm.quote(&QuoteParams {
+        amount,
+        input_mint,
+        output_mint,
+        swap_mode: SwapMode::ExactIn,
+    })
+    .unwrap()
+    .out_amount
+}
+
+#[test]
+fn test() {
+    let with_fee = pool(TOKEN_2022_ID, Some(100)).load().unwrap();
+    let without_fee = pool(TOKEN_2022_ID, None).load().unwrap();
+
+    // Only what arrives in the custody after the 1% fee is swapped
+    assert_eq!(
+        out_amount(&with_fee, FEE_TOKEN, USDC, 100_000_000),
+        out_amount(&without_fee, FEE_TOKEN, USDC, 99_000_000)
+    );
+
+    // And 1% of the way out is withheld before it reaches the user, rounded up
+    let out = out_amount(&without_fee, USDC, FEE_TOKEN, 100_000_000);
+    assert_eq!(
+        out_amount(&with_fee, USDC, FEE_TOKEN, 100_000_000),
+        out - (out * 100).div_ceil(10_000)
+    );
+
+    let owner = Pubkey::new_unique();
+    let swap_params = |source_mint: Pubkey, destination_mint: Pubkey| SwapParams {
+        in_amount: 1_000_000,
+        out_amount: 0,
+        source_mint,
+        destination_mint,
+        source_token_account: Pubkey::new_unique(),
+        destination_token_account: Pubkey::new_unique(),
+        token_transfer_authority: owner,
+        open_order_address: None,
+        quote_mint_to_referrer: None,
+        jupiter_program_id: &Pubkey::default(),
+        missing_dynamic_accounts_as_default: false,
+    };
+
+    // Every mint moved, the reward mint included, is a Token-2022 one
+    let metas = with_fee
+        .get_swap_and_account_metas(&swap_params(FEE_TOKEN, PLAIN_TOKEN))
+        .unwrap()
+        .account_metas;
+    assert!(metas.iter().any(|m| m.pubkey == TOKEN_2022_ID));
+    assert!(!metas.iter().any(|m| m.pubkey == SPL_TOKEN_ID));
+
+    // With USDC, the reward mint, left on the legacy program, a swap between two
+    // Token-2022 mints still needs both programs
+    let mixed = pool(SPL_TOKEN_ID, Some(100)).load().unwrap();
+    assert!(mixed
+        .get_swap_and_account_metas(&swap_params(FEE_TOKEN, PLAIN_TOKEN))
+        .is_err());
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use adrena::state::user_profile::UserProfile;
+use common::{program_account, usdc_wsol_pool, USDC, WSOL};
+use jupiter_adrena::{InMemoryAccountSource, PoolAmm, UserProfileState};
+use jupiter_amm_interface::{Amm, QuoteParams, SwapMode, SwapParams};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+
+    for mint in [USDC, WSOL] {
+        let custody = fixture.custody_mut(&mint);
+        custody.assets.owned = 1_000_000_000_000;
+        custody.fees.swap_in = 30;
+        custody.fees.swap_out = 30;
+    }
+
+    let mut amm = fixture.load().unwrap();
+    let owner = Pubkey::new_unique();
+    let referrer = Pubkey::new_unique();
+    let user_profile_key = amm.get_user_profile_key(&owner);
+    let user_token_accounts = amm.get_user_token_accounts(&owner);
+
+    let params = QuoteParams {
+        amount: 1_000_000_000,
+        input_mint: WSOL,
+        output_mint: USDC,
+        swap_mode: SwapMode::ExactIn,
+    };
+    let swap_metas = |amm: &PoolAmm| {
+        amm.get_swap_and_account_metas(&SwapParams {
+            in_amount: 1_000_000_000,
+            out_amount: 0,
+            source_mint: WSOL,
+            destination_mint: USDC,
+            source_token_account: user_token_accounts[&WSOL],
+            destination_token_account: user_token_accounts[&USDC],
+            token_transfer_authority: owner,
+            open_order_address: None,
+            quote_mint_to_referrer: None,
+            jupiter_program_id: &Pubkey::default(),
+            missing_dynamic_accounts_as_default: false,
+        })
+        .unwrap()
+        .account_metas
+    };
+
+    let has_profile_meta =
+        |amm: &PoolAmm| swap_metas(amm).iter().any(|m| m.pubkey == user_profile_key);
+
+    // Profiles never loaded get no discount, and are left out of the
+    // instruction as they may not exist
+    let base = amm.quote(&params).unwrap();
+    let unknown = amm.quote_for_user(&params, &owner).unwrap();
+    assert_eq!(unknown.out_amount, base.out_amount);
+    assert!(!has_profile_meta(&amm));
+
+    // So are profiles known not to exist
+    amm.load_user_profile(owner, None).unwrap();
+    assert!(amm.user_profile(&owner).is_none());
+    assert!(!has_profile_meta(&amm));
+
+    // A 25% discount on swap fees
+    let mut profile: UserProfile = bytemuck::Zeroable::zeroed();
+    profile.owner = owner;
+    profile.referrer_profile = referrer;
+    profile.fee_discount_bps = 2_500;
+    let profile_account = program_account(&profile);
+
+    let source = InMemoryAccountSource::new([(user_profile_key, profile_account.clone())].into());
+    amm.fetch_user_profile(&source, owner).unwrap();
+    assert_eq!(
+        amm.user_profile(&owner),
+        Some(&UserProfileState {
+            referrer: Some(referrer),
+            fee_discount_bps: 2_500,
+        })
+    );
+    assert!(has_profile_meta(&amm));
+
+    let discounted = amm.quote_for_user(&params, &owner).unwrap();
+    assert!(base.fee_amount > 0);
+    assert!(discounted.fee_amount < base.fee_amount);
+    assert!(discounted.out_amount > base.out_amount);
+
+    // Other users and plain quotes are unaffected
+    assert_eq!(amm.quote(&params).unwrap().out_amount, base.out_amount);
+    assert_eq!(
+        amm.quote_for_user(&params, &Pubkey::new_unique())
+            .unwrap()
+            .out_amount,
+        base.out_amount
+    );
+
+    // The quoting owner's profile is loaded on update and discounts `quote`
+    let mut source = fixture.source();
+    source
+        .accounts
+        .insert(user_profile_key, profile_account.clone());
+
+    let mut quoting = fixture.load().unwrap();
+    quoting.set_quoting_owner(Some(owner));
+    assert!(quoting.get_accounts_to_update().contains(&user_profile_key));
+    assert_eq!(quoting.quote(&params).unwrap().out_amount, base.out_amount);
+
+    quoting.refresh(&source).unwrap();
+    assert_eq!(quoting.user_profile(&owner), amm.user_profile(&owner));
+    assert_eq!(
+        quoting.quote(&params).unwrap().out_amount,
+        discounted.out_amount
+    );
+    assert!(has_profile_meta(&quoting));
+
+    // Restored snapshots quote for the same owner
+    let restored = PoolAmm::from_snapshot(&quoting.snapshot().unwrap()).unwrap();
+    assert_eq!(
+        restored.quote(&params).unwrap().out_amount,
+        discounted.out_amount
+    );
+
+    // Loaded profiles follow updates, including their closing
+    profile.fee_discount_bps = 5_000;
+    source
+        .accounts
+        .insert(user_profile_key, program_account(&profile));
+    amm.refresh(&source).unwrap();
+    assert_eq!(amm.user_profile(&owner).unwrap().fee_discount_bps, 5_000);
+    assert!(amm.quote_for_user(&params, &owner).unwrap().out_amount > discounted.out_amount);
+
+    source.accounts.remove(&user_profile_key);
+    for amm in [&mut amm, &mut quoting] {
+        amm.refresh(&source).unwrap();
+        assert!(amm.user_profile(&owner).is_none());
+        assert!(!has_profile_meta(amm));
+    }
+    assert_eq!(quoting.quote(&params).unwrap().out_amount, base.out_amount);
+
+    // Someone else's profile is refused
+    assert!(amm
+        .load_user_profile(Pubkey::new_unique(), Some(&profile_account))
+        .is_err());
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{usdc_wsol_pool, PROGRAM_ID, WSOL};
+use jupiter_adrena::{AccountValidationError, InMemoryAccountSource, PoolAmm};
+use jupiter_amm_interface::{AccountMap, Amm, AmmContext, ClockRef, KeyedAccount};
+use solana_sdk::pubkey::Pubkey;
+
+fn load_error(accounts: AccountMap, pool_key: Pubkey) -> AccountValidationError {
+    PoolAmm::load(&InMemoryAccountSource::new(accounts), pool_key, None)
+        .err()
+        .expect("Loading should fail")
+        .downcast_ref::<AccountValidationError>()
+        .expect("Should be a validation error")
+        .clone()
+}
+
+#[test]
+fn test() {
+    let mut fixture = usdc_wsol_pool();
+    let pool_key = fixture.pool_key;
+    let (custody_key, custody) = fixture.custodies[1];
+    let spoofer = Pubkey::new_unique();
+
+    // A pool owned by another program, even one deserializing fine, is not
+    // mistaken for an Adrena pool
+    let mut pool_account = fixture.source().accounts[&pool_key].clone();
+    pool_account.owner = spoofer;
+    let err = PoolAmm::from_keyed_account(
+        &KeyedAccount {
+            key: pool_key,
+            account: pool_account,
+            params: None,
+        },
+        &AmmContext {
+            clock_ref: ClockRef::default(),
+        },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.downcast_ref::<AccountValidationError>(),
+        Some(&AccountValidationError::InvalidOwner {
+            account: pool_key,
+            expected: PROGRAM_ID,
+            actual: spoofer,
+        })
+    );
+
+    let accounts = fixture.source().accounts;
+
+    let mut spoofed = accounts.clone();
+    spoofed.get_mut(&custody_key).unwrap().owner = spoofer;
+    assert_eq!(
+        load_error(spoofed, pool_key),
+        AccountValidationError::InvalidOwner {
+            account: custody_key,
+            expected: PROGRAM_ID,
+            actual: spoofer,
+        }
+    );
+
+    let mut truncated = accounts.clone();
+    let data = &mut truncated.get_mut(&custody_key).unwrap().data;
+    let len = data.len();
+    data.truncate(len - 1);
+    assert_eq!(
+        load_error(truncated, pool_key),
+        AccountValidationError::InvalidSize {
+            account: custody_key,
+            expected: len,
+            actual: len - 1,
+        }
+    );
+
+    // Accounts grown by a realloc still load, trailing bytes are ignored
+    let mut grown = accounts.clone();
+    grown
+        .get_mut(&custody_key)
+        .unwrap()
+        .data
+        .extend_from_slice(&[0; 64]);
+    assert!(PoolAmm::load(&InMemoryAccountSource::new(grown), pool_key, None).is_ok());
+
+    let mut bad_discriminator = accounts.clone();
+    bad_discriminator.get_mut(&custody_key).unwrap().data[0] ^= 0xff;
+    assert_eq!(
+        load_error(bad_discriminator, pool_key),
+        AccountValidationError::InvalidDiscriminator {
+            account: custody_key
+        }
+    );
+
+    let mut spoofed_oracle = accounts.clone();
+    spoofed_oracle
+        .get_mut(&custody.oracle.oracle_account)
+        .unwrap()
+        .owner = spoofer;
+    assert_eq!(
+        load_error(spoofed_oracle, pool_key),
+        AccountValidationError::InvalidOracleOwner {
+            account: custody.oracle.oracle_account,
+            actual: spoofer,
+        }
+    );
+
+    let mut spoofed_mint = accounts;
+    spoofed_mint.get_mut(&WSOL).unwrap().owner = spoofer;
+    assert_eq!(
+        load_error(spoofed_mint, pool_key),
+        AccountValidationError::InvalidTokenProgram {
+            account: WSOL,
+            actual: spoofer,
+        }
+    );
+
+    // A custody of another pool listed in this one
+    let other_pool = Pubkey::new_unique();
+    fixture.custody_mut(&WSOL).pool = other_pool;
+    assert_eq!(
+        load_error(fixture.source().accounts, pool_key),
+        AccountValidationError::CustodyPoolMismatch {
+            custody: custody_key,
+            expected: pool_key,
+            actual: other_pool,
+        }
+    );
+}